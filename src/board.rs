@@ -0,0 +1,61 @@
+//! Helpers shared by the games played on a grid of cells. Cells are written as
+//! the column letter followed by the row number, so `a1` is the top left cell.
+
+use std::fmt;
+
+/// Writes the given cell as its column letter followed by its row number.
+pub fn fmt_cell(f: &mut fmt::Formatter<'_>, col: u8, row: u8) -> fmt::Result {
+    write!(f, "{}{}", (b'a' + col) as char, row as u32 + 1)
+}
+
+/// Parses a cell written as its column letter followed by its row number, &
+/// returns its column & row. Returns None if the text isn't a cell.
+pub fn parse_cell(s: &str) -> Option<(u8, u8)> {
+    let mut chars = s.chars();
+    let col = match chars.next() {
+        Some(ch @ 'a'..='z') => ch as u8 - b'a',
+        _ => return None,
+    };
+    let row = match chars.as_str().parse::<u8>() {
+        Ok(row) if row > 0 => row - 1,
+        _ => return None,
+    };
+
+    Some((col, row))
+}
+
+/// Writes a board of the given size, with column letters along the top & row
+/// numbers down the side. Each cell is written as the player in it, or `.` if
+/// it is empty. If `staggered` is set, each row is shifted right of the one
+/// above, which lines up the cells of a hexagonal board.
+pub fn fmt_board<P, F>(
+    f: &mut fmt::Formatter<'_>,
+    width: usize,
+    height: usize,
+    staggered: bool,
+    get_cell: F,
+) -> fmt::Result
+where
+    P: fmt::Display,
+    F: Fn(usize, usize) -> Option<P>,
+{
+    write!(f, "  ")?;
+    for col in 0..width {
+        write!(f, " {}", (b'a' + col as u8) as char)?;
+    }
+
+    for row in 0..height {
+        write!(f, "\n{:>2}", row + 1)?;
+        if staggered {
+            write!(f, "{}", " ".repeat(row))?;
+        }
+        for col in 0..width {
+            match get_cell(col, row) {
+                Some(ply) => write!(f, " {}", ply)?,
+                None => write!(f, " .")?,
+            }
+        }
+    }
+
+    Ok(())
+}
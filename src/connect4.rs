@@ -1,42 +1,15 @@
-use crate::game::GameState;
-use std::fmt;
-
-/// The players available in connect 4.
-#[derive(Clone, Copy, PartialEq, Debug)]
-pub enum Player {
-    Red,
-    Yellow,
-}
-
-impl Player {
-    /// Returns a vec containing all available players.
-    pub fn all() -> Vec<Self> {
-        vec![Self::Red, Self::Yellow]
-    }
+pub mod bitboard;
+pub mod solver;
 
-    /// Returns the next player in the turn sequence.
-    pub fn next(self) -> Self {
-        match self {
-            Self::Red => Self::Yellow,
-            Self::Yellow => Self::Red,
-        }
-    }
-
-    /// Returns the previous player in the turn sequence.
-    pub fn prev(self) -> Self {
-        match self {
-            Self::Yellow => Self::Red,
-            Self::Red => Self::Yellow,
-        }
-    }
-}
+use crate::game::{two_players, zobrist_piece_key, GameState, ZOBRIST_TURN_KEY};
+use std::fmt;
+use std::hash::{Hash, Hasher};
 
-impl ToString for Player {
-    fn to_string(&self) -> String {
-        match self {
-            Self::Red => "R".to_owned(),
-            Self::Yellow => "Y".to_owned(),
-        }
+two_players! {
+    /// The players available in connect 4.
+    pub enum Player {
+        Red => "R",
+        Yellow => "Y",
     }
 }
 
@@ -45,7 +18,7 @@ impl ToString for Player {
 pub type Move = u8;
 
 /// The move errors possible in connect 4.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum MoveError {
     OutOfRange(Move),
     ColumnFull(Move),
@@ -60,17 +33,41 @@ impl fmt::Debug for MoveError {
     }
 }
 
-const WIDTH: usize = 7;
-const HEIGHT: usize = 6;
-const CONNECT_LEN: usize = 4;
+/// The width of a standard connect 4 board.
+pub const DEFAULT_WIDTH: usize = 7;
+/// The height of a standard connect 4 board.
+pub const DEFAULT_HEIGHT: usize = 6;
+/// The length of line needed to win standard connect 4.
+pub const DEFAULT_CONNECT_LEN: usize = 4;
+
+/// Panics if a board of the given size can't be played on.
+fn check_size(width: usize, height: usize, connect_len: usize) {
+    assert!(
+        width > 0 && height > 0,
+        "The board must have at least one cell"
+    );
+    assert!(
+        width <= Move::MAX as usize + 1,
+        "The board can have at most {} columns",
+        Move::MAX as usize + 1
+    );
+    assert!(connect_len > 0, "The connect length must be at least 1");
+}
 
 /// The connect 4 game state.
-// TODO: Move to const generics.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "persist", derive(serde::Serialize, serde::Deserialize))]
 pub struct Game {
     turn: Player,
-    board: [[Option<Player>; HEIGHT]; WIDTH],
+    width: usize,
+    height: usize,
+    connect_len: usize,
+    /// The cells of the board, stored column by column with the bottom row
+    /// first.
+    board: Vec<Option<Player>>,
     winner: Option<Player>,
+    /// The Zobrist hash of the position, which is updated on every move.
+    hash: u64,
 }
 
 #[derive(Clone, Copy)]
@@ -79,18 +76,69 @@ struct Point(usize, usize);
 struct PointDirection(i64, i64);
 
 impl Game {
-    /// Constructs a new connect 4 game state.
+    /// Constructs a new connect 4 game state on the standard 7x6 board.
     pub fn new() -> Self {
+        Self::with_size(DEFAULT_WIDTH, DEFAULT_HEIGHT, DEFAULT_CONNECT_LEN)
+    }
+
+    /// Constructs a new game state on a board of the given size, where a line
+    /// of `connect_len` pieces wins.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the board has no cells, has more columns than can be
+    /// referenced by a move, or if `connect_len` is 0.
+    pub fn with_size(width: usize, height: usize, connect_len: usize) -> Self {
+        check_size(width, height, connect_len);
         Game {
             turn: Player::Red,
-            board: [[None; HEIGHT]; WIDTH],
+            width,
+            height,
+            connect_len,
+            board: vec![None; width * height],
             winner: None,
+            hash: 0,
         }
     }
 
+    /// Returns the number of columns on the board.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the number of rows on the board.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the length of line needed to win.
+    pub fn connect_len(&self) -> usize {
+        self.connect_len
+    }
+
+    /// Returns the Zobrist hash of the current position.
+    pub fn zobrist_hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Returns the piece in the given cell, where row 0 is the bottom row.
+    pub fn get_cell(&self, col: usize, row: usize) -> Option<Player> {
+        self.board[self.cell_index(col, row)]
+    }
+
+    /// Returns the index of the given cell in the board.
+    fn cell_index(&self, col: usize, row: usize) -> usize {
+        col * self.height + row
+    }
+
+    /// Returns the cells of the given column, with the bottom row first.
+    fn column(&self, col: usize) -> &[Option<Player>] {
+        &self.board[col * self.height..(col + 1) * self.height]
+    }
+
     /// Updates the stored winner from the current board position.
     fn update_winner_from(&mut self, col: usize, row: usize) {
-        if let Some(ply) = self.board[col][row] {
+        if let Some(ply) = self.get_cell(col, row) {
             for &dir in &[
                 PointDirection(1, 0),
                 PointDirection(1, 1),
@@ -105,7 +153,7 @@ impl Game {
                     + self.count_line_from(start, dir, ply, false)
                     + self.count_line_from(start, dir, ply, true);
 
-                if count >= CONNECT_LEN as u64 {
+                if count >= self.connect_len as u64 {
                     self.winner = Some(ply);
                 }
             }
@@ -115,9 +163,9 @@ impl Game {
     /// Counts the number of pieces that are the same from the given direction.
     fn count_line_from(&self, start: Point, dir: PointDirection, player: Player, rev: bool) -> u64 {
         let mut count = 0;
-        for dist in 1..(CONNECT_LEN as i64) {
-            if let Some(Point(col, row)) = Self::get_point_from(start, dir, dist, rev) {
-                if let Some(ply) = self.board[col][row] {
+        for dist in 1..(self.connect_len as i64) {
+            if let Some(Point(col, row)) = self.get_point_from(start, dir, dist, rev) {
+                if let Some(ply) = self.get_cell(col, row) {
                     if ply == player {
                         count += 1;
                         continue;
@@ -132,12 +180,18 @@ impl Game {
 
     /// Gets a board on the board in a given direction & distance away from the
     /// centre point.
-    fn get_point_from(start: Point, dir: PointDirection, dist: i64, rev: bool) -> Option<Point> {
+    fn get_point_from(
+        &self,
+        start: Point,
+        dir: PointDirection,
+        dist: i64,
+        rev: bool,
+    ) -> Option<Point> {
         let Point(col_i, row_i) = start;
         let PointDirection(col_d, row_d) = dir;
 
-        let c_d = col_d as i64 * dist;
-        let r_d = row_d as i64 * dist;
+        let c_d = col_d * dist;
+        let r_d = row_d * dist;
 
         let (n_col, n_row) = if rev {
             (col_i as i64 - c_d, row_i as i64 - r_d)
@@ -145,7 +199,7 @@ impl Game {
             (col_i as i64 + c_d, row_i as i64 + r_d)
         };
 
-        if n_col >= 0 && n_col < WIDTH as i64 && n_row >= 0 && n_row < HEIGHT as i64 {
+        if n_col >= 0 && n_col < self.width as i64 && n_row >= 0 && n_row < self.height as i64 {
             Some(Point(n_col as usize, n_row as usize))
         } else {
             None
@@ -153,17 +207,24 @@ impl Game {
     }
 }
 
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl GameState<Player, Move, MoveError> for Game {
     fn make_move(&mut self, mv: Move) -> Result<(), MoveError> {
         let col_i = mv as usize;
-        if col_i >= WIDTH {
+        if col_i >= self.width {
             return Err(MoveError::OutOfRange(mv));
         }
 
-        let col = &mut self.board[col_i];
-        match col.iter().position(|&cell| cell == None) {
+        match self.column(col_i).iter().position(|&cell| cell.is_none()) {
             Some(row_i) => {
-                col[row_i] = Some(self.turn);
+                let cell = self.cell_index(col_i, row_i);
+                self.board[cell] = Some(self.turn);
+                self.hash ^= zobrist_piece_key(cell, self.turn.index()) ^ ZOBRIST_TURN_KEY;
                 self.update_winner_from(col_i, row_i);
                 self.turn = self.turn.next();
                 Ok(())
@@ -177,14 +238,11 @@ impl GameState<Player, Move, MoveError> for Game {
             Some(_) => vec![],
             None => self
                 .board
-                .iter()
-                .zip(0..WIDTH) // Zip in index.
+                .chunks(self.height)
+                .zip(0..self.width) // Zip in index.
                 // Filter out the columns that have a piece in the top slot.
                 // Columns without a piece here are guaranteed to have space.
-                .filter(|(col, _)| match col.last() {
-                    Some(None) => true,
-                    _ => false,
-                })
+                .filter(|(col, _)| matches!(col.last(), Some(None)))
                 .map(|(_, i)| i as Move) // Select the index of the column as the move.
                 .collect(),
         }
@@ -201,40 +259,154 @@ impl GameState<Player, Move, MoveError> for Game {
     fn get_prev_player(&self) -> Player {
         self.turn.prev()
     }
+
+    fn get_players(&self) -> Vec<Player> {
+        Player::all()
+    }
+
+    fn position_hash(&self) -> Option<u64> {
+        Some(self.hash)
+    }
+}
+
+impl PartialEq for Game {
+    fn eq(&self, other: &Self) -> bool {
+        // The winner is derived from the board, so it doesn't need comparing.
+        self.hash == other.hash
+            && self.turn == other.turn
+            && self.width == other.width
+            && self.height == other.height
+            && self.connect_len == other.connect_len
+            && self.board == other.board
+    }
+}
+
+impl Eq for Game {}
+
+impl Hash for Game {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
 }
 
 impl fmt::Display for Game {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut out = "".to_owned();
-        for row in (0..HEIGHT).rev() {
-            out = format!("{}{}", out, "|");
-            for col in 0..WIDTH {
-                out = format!(
-                    "{} {} |",
-                    out,
-                    match self.board[col][row] {
-                        Some(ply) => ply.to_string(),
-                        None => " ".to_owned(),
-                    }
-                )
-            }
+        fmt_board(f, self.width, self.height, |col, row| {
+            self.get_cell(col, row)
+        })
+    }
+}
+
+/// Writes the board with the pieces given by the cell fn, which is shared
+/// between each of the connect 4 implementations.
+fn fmt_board<F>(f: &mut fmt::Formatter<'_>, width: usize, height: usize, cell: F) -> fmt::Result
+where
+    F: Fn(usize, usize) -> Option<Player>,
+{
+    let mut out = "".to_owned();
+    for row in (0..height).rev() {
+        out = format!("{}{}", out, "|");
+        for col in 0..width {
             out = format!(
-                "{}{}",
+                "{} {} |",
                 out,
-                match row {
-                    r if r > 0 => "\n",
-                    _ => "",
+                match cell(col, row) {
+                    Some(ply) => ply.to_string(),
+                    None => " ".to_owned(),
                 }
-            );
+            )
         }
-
-        write!(
-            f,
-            "{}\n{}",
+        out = format!(
+            "{}{}",
             out,
-            (0..(WIDTH * 4))
-                .map(|_| "-")
-                .fold("-".to_owned(), |a, b| format!("{}{}", a, b))
-        )
+            match row {
+                r if r > 0 => "\n",
+                _ => "",
+            }
+        );
+    }
+
+    write!(
+        f,
+        "{}\n{}",
+        out,
+        (0..(width * 4))
+            .map(|_| "-")
+            .fold("-".to_owned(), |a, b| format!("{}{}", a, b))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Returns the game with the given moves played.
+    fn play(moves: &[Move]) -> Game {
+        let mut game = Game::new();
+        for &mv in moves {
+            game.make_move(mv).unwrap();
+        }
+        game
+    }
+
+    /// Returns the Zobrist hash of the given game, worked out from its board
+    /// & turn rather than move by move.
+    fn hash_from_scratch(game: &Game) -> u64 {
+        let mut hash = match game.turn {
+            Player::Red => 0,
+            Player::Yellow => ZOBRIST_TURN_KEY,
+        };
+        for (cell, piece) in game.board.iter().enumerate() {
+            if let Some(ply) = piece {
+                hash ^= zobrist_piece_key(cell, ply.index());
+            }
+        }
+        hash
+    }
+
+    #[test]
+    fn move_orders_reaching_the_same_position_are_equal() {
+        let a = play(&[0, 1, 2, 3]);
+        let b = play(&[2, 3, 0, 1]);
+        assert_eq!(a.zobrist_hash(), b.zobrist_hash());
+        assert_eq!(a.position_hash(), b.position_hash());
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn different_positions_hash_differently() {
+        // The same columns are filled, but by different players.
+        let a = play(&[0, 1]);
+        let b = play(&[1, 0]);
+        assert_ne!(a.zobrist_hash(), b.zobrist_hash());
+        assert_ne!(a, b);
+
+        // The same board with the other player to move.
+        let mut other_turn = a.clone();
+        other_turn.turn = other_turn.turn.next();
+        other_turn.hash ^= ZOBRIST_TURN_KEY;
+        assert_eq!(other_turn.zobrist_hash(), hash_from_scratch(&other_turn));
+        assert_ne!(other_turn.zobrist_hash(), a.zobrist_hash());
+
+        assert_ne!(play(&[3]).zobrist_hash(), play(&[3, 3]).zobrist_hash());
+        assert_ne!(Game::new().zobrist_hash(), play(&[3]).zobrist_hash());
+    }
+
+    proptest! {
+        /// Plays random games, checking the hash that is updated on every move
+        /// against the hash worked out from scratch.
+        #[test]
+        fn incremental_hash_matches_hash_from_scratch(
+            moves in prop::collection::vec(0u8..DEFAULT_WIDTH as u8, 0..60),
+        ) {
+            let mut game = Game::new();
+            prop_assert_eq!(game.zobrist_hash(), hash_from_scratch(&game));
+            for mv in moves {
+                if game.make_move(mv).is_ok() {
+                    prop_assert_eq!(game.zobrist_hash(), hash_from_scratch(&game));
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,237 @@
+//! A bitboard-backed connect 4 implementation, which is much faster to roll
+//! out than [`super::Game`] while following exactly the same rules.
+
+use super::{
+    check_size, fmt_board, Move, MoveError, Player, DEFAULT_CONNECT_LEN, DEFAULT_HEIGHT,
+    DEFAULT_WIDTH,
+};
+use crate::game::GameState;
+use std::fmt;
+
+/// Returns whether the given pieces contain a line of at least `connect_len`
+/// that goes through the given cell. `col_bits` is the number of bits used
+/// for each column.
+fn has_line_through(pieces: u64, cell: u64, col_bits: usize, connect_len: usize) -> bool {
+    // The shifts that move a piece one step along each direction a line can be
+    // made in: vertical, horizontal & both diagonals.
+    let directions = [1, col_bits, col_bits - 1, col_bits + 1];
+
+    directions.iter().any(|&dir| {
+        // Each bit left in the mask is the start of a line of the length
+        // checked so far.
+        let mut starts = pieces;
+        for dist in 1..connect_len {
+            starts &= pieces.checked_shr((dir * dist) as u32).unwrap_or(0);
+        }
+
+        // Spread the starts back over the whole of each line.
+        let mut lines = starts;
+        for dist in 1..connect_len {
+            lines |= starts.checked_shl((dir * dist) as u32).unwrap_or(0);
+        }
+        lines & cell != 0
+    })
+}
+
+/// The connect 4 game state, stored as one u64 mask of pieces per player.
+/// Each column takes up `height + 1` bits, with the bottom row in the lowest
+/// bit. The extra bit at the top of each column is always empty, so that lines
+/// can't wrap between columns.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "persist", derive(serde::Serialize, serde::Deserialize))]
+pub struct Game {
+    turn: Player,
+    width: usize,
+    height: usize,
+    connect_len: usize,
+    /// The pieces of each player, indexed by [`Player::index`].
+    pieces: [u64; 2],
+    winner: Option<Player>,
+}
+
+impl Game {
+    /// Constructs a new connect 4 game state on the standard 7x6 board.
+    pub fn new() -> Self {
+        Self::with_size(DEFAULT_WIDTH, DEFAULT_HEIGHT, DEFAULT_CONNECT_LEN)
+    }
+
+    /// Returns whether a board of the given size fits in a bitboard, which
+    /// needs `width * (height + 1)` to be at most 64.
+    pub fn fits(width: usize, height: usize) -> bool {
+        height
+            .checked_add(1)
+            .and_then(|column_bits| width.checked_mul(column_bits))
+            .is_some_and(|bits| bits <= 64)
+    }
+
+    /// Constructs a new game state on a board of the given size, where a line
+    /// of `connect_len` pieces wins.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the board has no cells, if `connect_len` is 0, or if the
+    /// board doesn't fit in the bitboard, which needs `width * (height + 1)`
+    /// to be at most 64.
+    pub fn with_size(width: usize, height: usize, connect_len: usize) -> Self {
+        check_size(width, height, connect_len);
+        assert!(
+            Self::fits(width, height),
+            "A {}x{} board doesn't fit in a bitboard",
+            width,
+            height
+        );
+        Game {
+            turn: Player::Red,
+            width,
+            height,
+            connect_len,
+            pieces: [0, 0],
+            winner: None,
+        }
+    }
+
+    /// Returns the number of columns on the board.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the number of rows on the board.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the length of line needed to win.
+    pub fn connect_len(&self) -> usize {
+        self.connect_len
+    }
+
+    /// Returns the piece in the given cell, where row 0 is the bottom row.
+    pub fn get_cell(&self, col: usize, row: usize) -> Option<Player> {
+        let cell = self.bottom_mask(col) << row;
+        Player::all()
+            .into_iter()
+            .find(|&ply| self.pieces[ply.index()] & cell != 0)
+    }
+
+    /// Returns the mask of every piece on the board.
+    fn occupied(&self) -> u64 {
+        self.pieces[0] | self.pieces[1]
+    }
+
+    /// Returns the number of bits used for each column.
+    fn col_bits(&self) -> usize {
+        self.height + 1
+    }
+
+    /// Returns the mask of the bottom cell of the given column.
+    fn bottom_mask(&self, col: usize) -> u64 {
+        1 << (col * self.col_bits())
+    }
+
+    /// Returns the mask of the top cell of the given column.
+    fn top_mask(&self, col: usize) -> u64 {
+        1 << (col * self.col_bits() + self.height - 1)
+    }
+
+    /// Returns the mask of every playable cell of the given column.
+    fn column_mask(&self, col: usize) -> u64 {
+        ((1 << self.height) - 1) << (col * self.col_bits())
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<&super::Game> for Game {
+    /// Converts an array-backed game into a bitboard with the same position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the board doesn't fit in a bitboard.
+    fn from(game: &super::Game) -> Self {
+        let mut bits = Self::with_size(game.width(), game.height(), game.connect_len());
+        for col in 0..game.width() {
+            for row in 0..game.height() {
+                if let Some(ply) = game.get_cell(col, row) {
+                    bits.pieces[ply.index()] |= bits.bottom_mask(col) << row;
+                }
+            }
+        }
+        bits.turn = game.get_current_player();
+        bits.winner = game.get_winner();
+        bits
+    }
+}
+
+impl GameState<Player, Move, MoveError> for Game {
+    fn make_move(&mut self, mv: Move) -> Result<(), MoveError> {
+        let col = mv as usize;
+        if col >= self.width {
+            return Err(MoveError::OutOfRange(mv));
+        }
+
+        let occupied = self.occupied();
+        if occupied & self.top_mask(col) != 0 {
+            return Err(MoveError::ColumnFull(mv));
+        }
+
+        // Adding the bottom cell to the column carries up to the lowest empty
+        // cell.
+        let cell = (occupied + self.bottom_mask(col)) & self.column_mask(col);
+        let pieces = &mut self.pieces[self.turn.index()];
+        *pieces |= cell;
+        if has_line_through(*pieces, cell, self.height + 1, self.connect_len) {
+            self.winner = Some(self.turn);
+        }
+        self.turn = self.turn.next();
+        Ok(())
+    }
+
+    fn get_moves(&self) -> Vec<Move> {
+        match self.get_winner() {
+            Some(_) => vec![],
+            None => {
+                let occupied = self.occupied();
+                (0..self.width)
+                    .filter(|&col| occupied & self.top_mask(col) == 0)
+                    .map(|col| col as Move)
+                    .collect()
+            }
+        }
+    }
+
+    fn get_winner(&self) -> Option<Player> {
+        self.winner
+    }
+
+    fn get_current_player(&self) -> Player {
+        self.turn
+    }
+
+    fn get_prev_player(&self) -> Player {
+        self.turn.prev()
+    }
+
+    fn get_players(&self) -> Vec<Player> {
+        Player::all()
+    }
+
+    fn position_hash(&self) -> Option<u64> {
+        // The red pieces plus every occupied cell is unique for each position,
+        // since the sum carries into the cell above each column differently.
+        // Boards of different sizes can share hashes, but are never part of
+        // the same search.
+        Some(self.pieces[0] + self.occupied())
+    }
+}
+
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_board(f, self.width, self.height, |col, row| {
+            self.get_cell(col, row)
+        })
+    }
+}
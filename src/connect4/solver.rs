@@ -0,0 +1,206 @@
+//! A perfect connect 4 solver, which gives the exact game-theoretic value of
+//! a position. This is used as the ground truth to check the moves picked by
+//! MCTS against.
+//!
+//! The solver searches bitboards, so positions of the array-backed
+//! [`Game`] are converted first by [`Solver::solve_game`], which
+//! only works for boards that fit in a bitboard.
+
+use super::{bitboard, Game, Move};
+use crate::game::GameState;
+use std::collections::HashMap;
+
+/// The game-theoretic value of a position for the player whose turn it is,
+/// assuming perfect play from both players.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Value {
+    Loss,
+    Draw,
+    Win,
+}
+
+impl Value {
+    /// Converts a negamax score into the value it represents.
+    fn from_score(score: i8) -> Self {
+        match score {
+            s if s > 0 => Self::Win,
+            s if s < 0 => Self::Loss,
+            _ => Self::Draw,
+        }
+    }
+}
+
+/// The result of solving a single position.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Solution {
+    /// The value of the position for the player whose turn it is.
+    pub value: Value,
+    /// A move that keeps the value of the position, or `None` if the game has
+    /// already ended.
+    pub best_move: Option<Move>,
+    /// The number of positions searched to find the solution, not counting
+    /// positions answered by the transposition table.
+    pub nodes: u64,
+}
+
+/// The bounds on the score of a position that are known from earlier searches.
+#[derive(Clone, Copy)]
+struct Entry {
+    lower: i8,
+    upper: i8,
+    best_move: Option<Move>,
+}
+
+/// A negamax solver with alpha-beta pruning, a transposition table & move
+/// ordering. The transposition table is kept between calls to
+/// [`Solver::solve`], so solving positions from the same game gets faster
+/// as it goes.
+///
+/// The table is never trimmed, and keeps an entry for every position searched
+/// until it is emptied with [`Solver::clear`] or a board of another size is
+/// solved. Use [`Solver::table_size`] to keep an eye on it when solving many
+/// positions of large boards.
+#[derive(Default)]
+pub struct Solver {
+    table: HashMap<u64, Entry>,
+    /// The width, height & connect length of the positions in the table, since
+    /// position hashes are only unique for a single board size.
+    size: Option<(usize, usize, usize)>,
+    nodes: u64,
+}
+
+impl Solver {
+    /// Constructs a new solver with an empty transposition table.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the number of positions in the transposition table.
+    pub fn table_size(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Clears the transposition table.
+    pub fn clear(&mut self) {
+        self.table.clear();
+        self.size = None;
+    }
+
+    /// Solves the given position. This is an exhaustive search, so it can take
+    /// a long time for positions near the start of large boards.
+    pub fn solve(&mut self, game: &bitboard::Game) -> Solution {
+        let size = (game.width(), game.height(), game.connect_len());
+        if self.size != Some(size) {
+            self.clear();
+            self.size = Some(size);
+        }
+
+        self.nodes = 0;
+        let (score, best_move) = self.negamax(game, -1, 1);
+        Solution {
+            value: Value::from_score(score),
+            best_move,
+            nodes: self.nodes,
+        }
+    }
+
+    /// Solves the given position of the array-backed game by converting it
+    /// into a bitboard.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the board doesn't fit in a bitboard.
+    pub fn solve_game(&mut self, game: &Game) -> Solution {
+        self.solve(&game.into())
+    }
+
+    // Search fns.
+
+    /// Returns the score of the position for the player whose turn it is,
+    /// along with the move that reaches it. Scores outside of the alpha-beta
+    /// window are only bounds on the real score.
+    fn negamax(
+        &mut self,
+        game: &bitboard::Game,
+        mut alpha: i8,
+        mut beta: i8,
+    ) -> (i8, Option<Move>) {
+        self.nodes += 1;
+
+        // The previous player made the winning move.
+        if game.get_winner().is_some() {
+            return (-1, None);
+        }
+        let moves = game.get_moves();
+        if moves.is_empty() {
+            return (0, None);
+        }
+
+        let (orig_alpha, orig_beta) = (alpha, beta);
+        let key = game.position_hash().unwrap();
+        let mut tt_move = None;
+        if let Some(entry) = self.table.get(&key) {
+            alpha = alpha.max(entry.lower);
+            beta = beta.min(entry.upper);
+            if alpha >= beta {
+                return (alpha, entry.best_move);
+            }
+            tt_move = entry.best_move;
+        }
+
+        let mut best = (i8::MIN, None);
+        for mv in Self::order_moves(game, moves, tt_move) {
+            let mut child = game.clone();
+            child.make_move(mv).unwrap();
+            let score = -self.negamax(&child, -beta, -alpha).0;
+
+            if score > best.0 {
+                best = (score, Some(mv));
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        // Scores at or outside of the window only bound the real score.
+        let (lower, upper) = match best.0 {
+            s if s <= orig_alpha => (-1, s),
+            s if s >= orig_beta => (s, 1),
+            s => (s, s),
+        };
+        self.table.insert(
+            key,
+            Entry {
+                lower,
+                upper,
+                best_move: best.1,
+            },
+        );
+        best
+    }
+
+    /// Orders the moves so that the most promising are searched first: the
+    /// best move from an earlier search, then any move that wins straight away,
+    /// then the rest from the centre column outwards.
+    fn order_moves(
+        game: &bitboard::Game,
+        mut moves: Vec<Move>,
+        tt_move: Option<Move>,
+    ) -> Vec<Move> {
+        let width = game.width() as i64;
+        moves.sort_by_cached_key(|&mv| {
+            let wins = {
+                let mut child = game.clone();
+                child.make_move(mv).unwrap();
+                child.get_winner().is_some()
+            };
+            (
+                Some(mv) != tt_move,
+                !wins,
+                (2 * mv as i64 - (width - 1)).abs(),
+            )
+        });
+        moves
+    }
+}
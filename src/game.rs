@@ -14,6 +14,7 @@ where
     ME: Copy + fmt::Debug,
 {
     /// Returns a new game state that has the given move performed.
+    #[allow(clippy::wrong_self_convention)]
     fn from_move(&self, mv: M) -> Result<Self, ME> {
         let mut new_state = self.clone();
         new_state.make_move(mv)?;
@@ -28,6 +29,123 @@ where
     fn get_winner(&self) -> Option<P>;
     /// Returns the current player.
     fn get_current_player(&self) -> P;
-    /// Returns the previous player.
+    /// Returns the player that made the last move. This is the player of the
+    /// root node of a new search tree, so games where a player can move twice
+    /// in a row can't assume that it is the player before the current one.
     fn get_prev_player(&self) -> P;
+
+    /// Returns every player in the game. Defaults to the current & previous
+    /// players, which is correct for two-player games.
+    fn get_players(&self) -> Vec<P> {
+        vec![self.get_current_player(), self.get_prev_player()]
+    }
+
+    /// Returns the reward of each player once the game has ended, where 1 is
+    /// the best possible result & 0 the worst. Defaults to None, meaning that
+    /// rewards are derived from the winner instead.
+    fn get_rewards(&self) -> Option<Vec<(P, f64)>> {
+        None
+    }
+
+    /// Returns the outcomes of the chance event that happens next, such as a
+    /// die roll, along with the probability of each. The probabilities must
+    /// add up to 1. Defaults to None, meaning that a player moves next.
+    ///
+    /// While a chance event is pending, [`GameState::get_moves`] must return
+    /// the moves of every outcome & [`GameState::make_move`] must accept them.
+    /// The current player is the player that the chance event happens to.
+    fn get_chance_outcomes(&self) -> Option<Vec<(M, f64)>> {
+        None
+    }
+
+    /// Returns a hash of the current position, which is used to find states
+    /// that are reached through different move orders. Defaults to None,
+    /// meaning that the position can't be hashed.
+    fn position_hash(&self) -> Option<u64> {
+        None
+    }
 }
+
+/// Returns the Zobrist key for the given index, which games can use to hash
+/// their positions by XORing together the key of each piece on the board.
+/// Keys are generated with SplitMix64, so they are the same on every run.
+pub fn zobrist_key(index: u64) -> u64 {
+    let mut z = index.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// The Zobrist key that two-player games toggle whenever the turn changes, so
+/// that the same board with a different player to move hashes differently.
+/// Positions of different games are never compared, so they can share it.
+pub const ZOBRIST_TURN_KEY: u64 = 0xD6E8_FEB8_6659_FD93;
+
+/// Returns the Zobrist key for a piece of the player with the given index in
+/// the given cell index of a two-player game, where the player index is 0 or
+/// 1.
+pub fn zobrist_piece_key(cell: usize, player_index: usize) -> u64 {
+    zobrist_key((cell * 2 + player_index) as u64)
+}
+
+/// Defines the player type of a two-player game, with its two players in turn
+/// order & the text that each is displayed as. The type gets the fns used to
+/// step through the turn sequence.
+macro_rules! two_players {
+    (
+        $(#[$attr:meta])*
+        pub enum $name:ident {
+            $(#[$first_attr:meta])* $first:ident => $first_str:literal,
+            $(#[$second_attr:meta])* $second:ident => $second_str:literal $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+        #[cfg_attr(feature = "persist", derive(serde::Serialize, serde::Deserialize))]
+        pub enum $name {
+            $(#[$first_attr])* $first,
+            $(#[$second_attr])* $second,
+        }
+
+        impl $name {
+            /// Returns a vec containing all available players.
+            pub fn all() -> Vec<Self> {
+                vec![Self::$first, Self::$second]
+            }
+
+            /// Returns the next player in the turn sequence.
+            pub fn next(self) -> Self {
+                match self {
+                    Self::$first => Self::$second,
+                    Self::$second => Self::$first,
+                }
+            }
+
+            /// Returns the previous player in the turn sequence. With two
+            /// players, this is the same as the next player.
+            pub fn prev(self) -> Self {
+                self.next()
+            }
+
+            /// Returns the position of the player in the turn sequence,
+            /// starting from 0.
+            pub fn index(self) -> usize {
+                match self {
+                    Self::$first => 0,
+                    Self::$second => 1,
+                }
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    Self::$first => write!(f, $first_str),
+                    Self::$second => write!(f, $second_str),
+                }
+            }
+        }
+    };
+}
+
+pub(crate) use two_players;
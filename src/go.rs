@@ -0,0 +1,524 @@
+//! Go on square boards of up to 19x19, although the search is only really
+//! practical on 9x9 & smaller. Stones with no liberties are captured, moves
+//! that would repeat an earlier position are illegal (positional superko), & the
+//! game ends once both players pass in a row. The winner is decided by area
+//! scoring, with komi given to white, & the rewards of each player grow with
+//! the margin of the result.
+
+use crate::board;
+use crate::game::{two_players, zobrist_piece_key, GameState};
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+two_players! {
+    /// The players available in Go.
+    pub enum Player {
+        Black => "B",
+        White => "W",
+    }
+}
+
+/// The moves available in Go. Placed stones are written as the column letter
+/// followed by the row number, so `a1` is the top left point, & passes are
+/// written as `pass`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "persist", derive(serde::Serialize, serde::Deserialize))]
+pub enum Move {
+    /// Places a stone on the given point.
+    Place { col: u8, row: u8 },
+    /// Passes the turn to the other player. Two passes in a row end the game.
+    Pass,
+}
+
+impl Move {
+    /// Constructs a move that places a stone on the given point.
+    pub fn place(col: u8, row: u8) -> Self {
+        Move::Place { col, row }
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Place { col, row } => board::fmt_cell(f, *col, *row),
+            Self::Pass => write!(f, "pass"),
+        }
+    }
+}
+
+impl fmt::Debug for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl FromStr for Move {
+    type Err = ParseMoveError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "pass" {
+            return Ok(Move::Pass);
+        }
+
+        let (col, row) = board::parse_cell(s).ok_or(ParseMoveError)?;
+        Ok(Move::place(col, row))
+    }
+}
+
+/// The error returned when a move can't be parsed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ParseMoveError;
+
+impl fmt::Display for ParseMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Moves are a column letter followed by a row number, or 'pass'"
+        )
+    }
+}
+
+/// The move errors possible in Go.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MoveError {
+    OutOfRange(Move),
+    Occupied(Move),
+    /// The stone would have no liberties without capturing anything.
+    Suicide(Move),
+    /// The move would repeat an earlier position.
+    Superko(Move),
+    /// Both players have already passed.
+    GameOver,
+}
+
+impl fmt::Debug for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfRange(mv) => write!(f, "Move {} is out of range", mv),
+            Self::Occupied(mv) => write!(f, "Point {} is already taken", mv),
+            Self::Suicide(mv) => write!(f, "Move {} would be suicide", mv),
+            Self::Superko(mv) => write!(f, "Move {} would repeat an earlier position", mv),
+            Self::GameOver => write!(f, "The game is over"),
+        }
+    }
+}
+
+/// The standard size of a small board, which is the largest that the search
+/// copes with well.
+pub const DEFAULT_SIZE: usize = 9;
+
+/// The largest board size supported.
+pub const MAX_SIZE: usize = 19;
+
+/// The standard komi under area scoring, which is added to white's score to
+/// make up for black moving first.
+pub const DEFAULT_KOMI: f64 = 7.5;
+
+/// The reward of a player that wins by the smallest possible margin. Rewards
+/// grow from this to 1 for a win by the whole area of the board, so any win is
+/// worth more than any loss, but bigger wins are still preferred.
+pub const MIN_WIN_REWARD: f64 = 0.9;
+
+/// The groups of stones on the board, along with the number of liberties of
+/// each.
+struct Groups {
+    /// The index of the group of each point, or None if the point is empty.
+    ids: Vec<Option<usize>>,
+    /// The number of liberties of each group.
+    liberties: Vec<usize>,
+}
+
+/// The Go game state.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "persist", derive(serde::Serialize, serde::Deserialize))]
+pub struct Game {
+    turn: Player,
+    size: usize,
+    komi: f64,
+    /// The points of the board, stored row by row with the top row first.
+    board: Vec<Option<Player>>,
+    /// The number of passes in a row, where two end the game.
+    passes: u8,
+    /// The Zobrist hash of the stones on the board, which doesn't include the
+    /// turn so that it can be checked for superko.
+    hash: u64,
+    /// The hashes of every position the board has been in, including the
+    /// current one.
+    history: HashSet<u64>,
+}
+
+impl Game {
+    /// Constructs a new game on a 9x9 board with the standard komi.
+    pub fn new() -> Self {
+        Self::with_size(DEFAULT_SIZE, DEFAULT_KOMI)
+    }
+
+    /// Constructs a new game on a board of the given size, where white is given
+    /// the given komi.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size is 0 or more than 19.
+    pub fn with_size(size: usize, komi: f64) -> Self {
+        assert!(
+            size > 0 && size <= MAX_SIZE,
+            "The board size must be between 1 & 19"
+        );
+
+        Game {
+            turn: Player::Black,
+            size,
+            komi,
+            board: vec![None; size * size],
+            passes: 0,
+            hash: 0,
+            history: vec![0].into_iter().collect(),
+        }
+    }
+
+    /// Returns the number of rows & columns on the board.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the komi given to white.
+    pub fn komi(&self) -> f64 {
+        self.komi
+    }
+
+    /// Returns the stone on the given point, where row 0 is the top row.
+    pub fn get_cell(&self, col: usize, row: usize) -> Option<Player> {
+        self.board[self.point_index(col, row)]
+    }
+
+    /// Returns the number of liberties of the group containing the stone on
+    /// the given point, or None if the point is empty.
+    pub fn liberties(&self, col: usize, row: usize) -> Option<usize> {
+        let point = self.point_index(col, row);
+        self.board[point].map(|_| self.group(point).1)
+    }
+
+    /// Returns the area score of the given player, which is the number of
+    /// their stones on the board plus the empty points that only they
+    /// surround. Komi is included in white's score.
+    pub fn score(&self, player: Player) -> f64 {
+        let stones = self.board.iter().filter(|&&p| p == Some(player)).count();
+
+        // Flood fill each empty region & note which players border it.
+        let mut territory = 0;
+        let mut seen = vec![false; self.board.len()];
+        for start in 0..self.board.len() {
+            if seen[start] || self.board[start].is_some() {
+                continue;
+            }
+
+            let (mut size, mut borders_own, mut borders_opp) = (0, false, false);
+            let mut stack = vec![start];
+            seen[start] = true;
+            while let Some(point) = stack.pop() {
+                size += 1;
+                for next in self.neighbours(point) {
+                    match self.board[next] {
+                        Some(ply) if ply == player => borders_own = true,
+                        Some(_) => borders_opp = true,
+                        None if !seen[next] => {
+                            seen[next] = true;
+                            stack.push(next);
+                        }
+                        None => {}
+                    }
+                }
+            }
+
+            if borders_own && !borders_opp {
+                territory += size;
+            }
+        }
+
+        let komi = match player {
+            Player::Black => 0.0,
+            Player::White => self.komi,
+        };
+        (stones + territory) as f64 + komi
+    }
+
+    /// Returns whether both players have passed in a row, which ends the game.
+    pub fn is_over(&self) -> bool {
+        self.passes >= 2
+    }
+
+    /// Returns the area score of black minus the area score of white.
+    pub fn margin(&self) -> f64 {
+        self.score(Player::Black) - self.score(Player::White)
+    }
+
+    /// Returns the index of the given point in the board.
+    fn point_index(&self, col: usize, row: usize) -> usize {
+        row * self.size + col
+    }
+
+    /// Returns the points next to the given point.
+    fn neighbours(&self, point: usize) -> impl Iterator<Item = usize> {
+        let (size, col, row) = (self.size, point % self.size, point / self.size);
+        let up = if row > 0 { Some(point - size) } else { None };
+        let down = if row + 1 < size {
+            Some(point + size)
+        } else {
+            None
+        };
+        let left = if col > 0 { Some(point - 1) } else { None };
+        let right = if col + 1 < size {
+            Some(point + 1)
+        } else {
+            None
+        };
+        up.into_iter().chain(down).chain(left).chain(right)
+    }
+
+    /// Returns the stones in the group containing the stone on the given
+    /// point, along with the number of liberties the group has.
+    fn group(&self, point: usize) -> (Vec<usize>, usize) {
+        let player = self.board[point];
+        let mut stones = vec![point];
+        let mut liberties = 0;
+        let mut seen = vec![false; self.board.len()];
+        seen[point] = true;
+
+        let mut i = 0;
+        while i < stones.len() {
+            for next in self.neighbours(stones[i]) {
+                if seen[next] {
+                    continue;
+                }
+                seen[next] = true;
+                match self.board[next] {
+                    None => liberties += 1,
+                    ply if ply == player => stones.push(next),
+                    Some(_) => {}
+                }
+            }
+            i += 1;
+        }
+
+        (stones, liberties)
+    }
+
+    /// Returns every group of stones on the board along with its liberties,
+    /// which is found with a single pass over the board so that every point
+    /// can be checked against it cheaply.
+    fn groups(&self) -> Groups {
+        let mut ids = vec![None; self.board.len()];
+        let mut liberties = vec![];
+        // The last group that each empty point was counted as a liberty of,
+        // so that each liberty is only counted once per group.
+        let mut counted = vec![usize::MAX; self.board.len()];
+        let mut stack = vec![];
+        for start in 0..self.board.len() {
+            if ids[start].is_some() || self.board[start].is_none() {
+                continue;
+            }
+
+            let id = liberties.len();
+            let mut group_liberties = 0;
+            ids[start] = Some(id);
+            stack.push(start);
+            while let Some(point) = stack.pop() {
+                for next in self.neighbours(point) {
+                    match self.board[next] {
+                        None if counted[next] != id => {
+                            counted[next] = id;
+                            group_liberties += 1;
+                        }
+                        ply if ply == self.board[start] && ids[next].is_none() => {
+                            ids[next] = Some(id);
+                            stack.push(next);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            liberties.push(group_liberties);
+        }
+
+        Groups { ids, liberties }
+    }
+
+    /// Checks whether the current player can place a stone on the given empty
+    /// point, using the groups of the current board. If they can, returns the
+    /// stones that would be captured & the hash of the resulting position.
+    fn check_placement(
+        &self,
+        mv: Move,
+        point: usize,
+        groups: &Groups,
+    ) -> Result<(Vec<usize>, u64), MoveError> {
+        let (own, opp) = (self.turn, self.turn.next());
+        let mut captured: Vec<usize> = vec![];
+        let mut captured_ids: Vec<usize> = vec![];
+        let mut has_liberty = false;
+        for next in self.neighbours(point) {
+            let id = match groups.ids[next] {
+                Some(id) => id,
+                None => {
+                    has_liberty = true;
+                    continue;
+                }
+            };
+            match self.board[next] {
+                // The new stone also takes one of the group's liberties.
+                Some(ply) if ply == own => has_liberty |= groups.liberties[id] > 1,
+                _ if groups.liberties[id] == 1 && !captured_ids.contains(&id) => {
+                    captured_ids.push(id);
+                    captured.extend(self.group(next).0);
+                }
+                _ => {}
+            }
+        }
+
+        if !has_liberty && captured.is_empty() {
+            return Err(MoveError::Suicide(mv));
+        }
+
+        let hash = captured.iter().fold(
+            self.hash ^ zobrist_piece_key(point, own.index()),
+            |hash, &stone| hash ^ zobrist_piece_key(stone, opp.index()),
+        );
+        if self.history.contains(&hash) {
+            return Err(MoveError::Superko(mv));
+        }
+
+        Ok((captured, hash))
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GameState<Player, Move, MoveError> for Game {
+    fn make_move(&mut self, mv: Move) -> Result<(), MoveError> {
+        if self.is_over() {
+            return Err(MoveError::GameOver);
+        }
+
+        match mv {
+            Move::Place { col, row } => {
+                let (col, row) = (col as usize, row as usize);
+                if col >= self.size || row >= self.size {
+                    return Err(MoveError::OutOfRange(mv));
+                }
+
+                let point = self.point_index(col, row);
+                if self.board[point].is_some() {
+                    return Err(MoveError::Occupied(mv));
+                }
+
+                let (captured, hash) = self.check_placement(mv, point, &self.groups())?;
+                self.board[point] = Some(self.turn);
+                for stone in captured {
+                    self.board[stone] = None;
+                }
+                self.hash = hash;
+                self.history.insert(hash);
+                self.passes = 0;
+            }
+            Move::Pass => self.passes += 1,
+        }
+
+        self.turn = self.turn.next();
+        Ok(())
+    }
+
+    fn get_moves(&self) -> Vec<Move> {
+        if self.is_over() {
+            return vec![];
+        }
+
+        // Most points have an empty neighbour & capture nothing, so once the
+        // groups are known, checking them doesn't need any flood fills.
+        let groups = self.groups();
+        let mut moves: Vec<Move> = (0..self.board.len())
+            .filter(|&point| self.board[point].is_none())
+            .map(|point| {
+                let mv = Move::place((point % self.size) as u8, (point / self.size) as u8);
+                (point, mv)
+            })
+            .filter(|&(point, mv)| self.check_placement(mv, point, &groups).is_ok())
+            .map(|(_, mv)| mv)
+            .collect();
+        moves.push(Move::Pass);
+        moves
+    }
+
+    fn get_winner(&self) -> Option<Player> {
+        if !self.is_over() {
+            return None;
+        }
+
+        let margin = self.margin();
+        if margin > 0.0 {
+            Some(Player::Black)
+        } else if margin < 0.0 {
+            Some(Player::White)
+        } else {
+            None
+        }
+    }
+
+    fn get_current_player(&self) -> Player {
+        self.turn
+    }
+
+    fn get_prev_player(&self) -> Player {
+        self.turn.prev()
+    }
+
+    fn get_players(&self) -> Vec<Player> {
+        Player::all()
+    }
+
+    /// Rewards the winner with between [`MIN_WIN_REWARD`] & 1, depending on
+    /// how much of the board they won by, & the loser with the rest. Drawn
+    /// games have no rewards, so that they are given the draw value.
+    fn get_rewards(&self) -> Option<Vec<(Player, f64)>> {
+        let margin = self.margin();
+        if !self.is_over() || margin == 0.0 {
+            return None;
+        }
+
+        let area = self.board.len() as f64;
+        let winner_reward =
+            MIN_WIN_REWARD + (1.0 - MIN_WIN_REWARD) * (margin.abs() / area).min(1.0);
+        let black_reward = if margin > 0.0 {
+            winner_reward
+        } else {
+            1.0 - winner_reward
+        };
+        Some(vec![
+            (Player::Black, black_reward),
+            (Player::White, 1.0 - black_reward),
+        ])
+    }
+
+    // There is no position hash, since superko makes the moves available
+    // depend on every earlier position as well as the board, so positions
+    // reached through different move orders can't be shared.
+}
+
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        board::fmt_board(f, self.size, self.size, false, |col, row| {
+            self.get_cell(col, row)
+        })?;
+        write!(
+            f,
+            "\nB: {}, W: {} (komi {})",
+            self.score(Player::Black),
+            self.score(Player::White),
+            self.komi
+        )
+    }
+}
@@ -0,0 +1,380 @@
+//! Hex, where players take turns placing a stone on a rhombus of hexagonal
+//! cells. Red wins by connecting the top & bottom edges, & blue by connecting
+//! the left & right edges. The board can't fill up without one of them
+//! connecting, so there are no draws.
+//!
+//! With the swap (pie) rule enabled, blue can take over red's first stone
+//! instead of placing their own, which stops red from simply opening in the
+//! centre.
+
+use crate::board;
+use crate::game::{two_players, zobrist_piece_key, GameState, ZOBRIST_TURN_KEY};
+use std::fmt;
+use std::str::FromStr;
+
+two_players! {
+    /// The players available in Hex.
+    pub enum Player {
+        /// Connects the top & bottom edges, & moves first.
+        Red => "R",
+        /// Connects the left & right edges.
+        Blue => "B",
+    }
+}
+
+/// The moves available in Hex. Placed stones are written as the column letter
+/// followed by the row number, so `a1` is the top left cell, & the swap is
+/// written as `swap`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "persist", derive(serde::Serialize, serde::Deserialize))]
+pub enum Move {
+    /// Places a stone in the given cell.
+    Place { col: u8, row: u8 },
+    /// Replaces red's first stone with a blue stone in the mirrored cell, as
+    /// blue's first move. This is only available with the swap rule.
+    Swap,
+}
+
+impl Move {
+    /// Constructs a move that places a stone in the given cell.
+    pub fn place(col: u8, row: u8) -> Self {
+        Move::Place { col, row }
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Place { col, row } => board::fmt_cell(f, *col, *row),
+            Self::Swap => write!(f, "swap"),
+        }
+    }
+}
+
+impl fmt::Debug for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl FromStr for Move {
+    type Err = ParseMoveError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "swap" {
+            return Ok(Move::Swap);
+        }
+
+        let (col, row) = board::parse_cell(s).ok_or(ParseMoveError)?;
+        Ok(Move::place(col, row))
+    }
+}
+
+/// The error returned when a move can't be parsed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ParseMoveError;
+
+impl fmt::Display for ParseMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Moves are a column letter followed by a row number, or 'swap'"
+        )
+    }
+}
+
+/// The move errors possible in Hex.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MoveError {
+    OutOfRange(Move),
+    Occupied(Move),
+    /// The swap rule is off, or this isn't blue's first move.
+    CannotSwap,
+}
+
+impl fmt::Debug for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfRange(mv) => write!(f, "Move {} is out of range", mv),
+            Self::Occupied(mv) => write!(f, "Cell {} is already taken", mv),
+            Self::CannotSwap => write!(f, "Swapping is only allowed as blue's first move"),
+        }
+    }
+}
+
+/// The standard size of the board.
+pub const DEFAULT_SIZE: usize = 11;
+
+/// The largest board size supported, since moves reference columns by letter.
+pub const MAX_SIZE: usize = 26;
+
+/// A disjoint-set forest over the cells of the board, plus a node for each
+/// edge, so that checking whether two edges are connected is almost constant
+/// time.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "persist", derive(serde::Serialize, serde::Deserialize))]
+struct UnionFind {
+    parents: Vec<usize>,
+    /// The number of nodes in the set of each root, which keeps the trees
+    /// shallow by always attaching the smaller set to the larger.
+    sizes: Vec<usize>,
+}
+
+impl UnionFind {
+    /// Constructs a forest where every node is in its own set.
+    fn new(len: usize) -> Self {
+        UnionFind {
+            parents: (0..len).collect(),
+            sizes: vec![1; len],
+        }
+    }
+
+    /// Returns the root of the set containing the given node, halving the path
+    /// to it along the way.
+    fn find(&mut self, mut node: usize) -> usize {
+        while self.parents[node] != node {
+            self.parents[node] = self.parents[self.parents[node]];
+            node = self.parents[node];
+        }
+        node
+    }
+
+    /// Joins the sets containing the two given nodes.
+    fn union(&mut self, a: usize, b: usize) {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        if self.sizes[a] < self.sizes[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parents[b] = a;
+        self.sizes[a] += self.sizes[b];
+    }
+}
+
+/// The Hex game state.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "persist", derive(serde::Serialize, serde::Deserialize))]
+pub struct Game {
+    turn: Player,
+    size: usize,
+    swap_rule: bool,
+    /// The cells of the board, stored row by row with the top row first. Each
+    /// row is shifted half a cell right of the one above, so a cell touches
+    /// the cells to its left & right, the two above it in its own column & the
+    /// next column along, & the two below it in its own column & the previous
+    /// column.
+    board: Vec<Option<Player>>,
+    /// The number of moves made so far, including any swap.
+    moves_made: usize,
+    /// The connected groups of stones, where the last four nodes are the top,
+    /// bottom, left & right edges.
+    groups: UnionFind,
+    winner: Option<Player>,
+    /// The Zobrist hash of the position, which is updated on every move.
+    hash: u64,
+}
+
+impl Game {
+    /// Constructs a new game on the standard 11x11 board, without the swap
+    /// rule.
+    pub fn new() -> Self {
+        Self::with_size(DEFAULT_SIZE)
+    }
+
+    /// Constructs a new game on a board of the given size, without the swap
+    /// rule.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size is 0 or more than 26.
+    pub fn with_size(size: usize) -> Self {
+        assert!(
+            size > 0 && size <= MAX_SIZE,
+            "The board size must be between 1 & 26"
+        );
+
+        Game {
+            turn: Player::Red,
+            size,
+            swap_rule: false,
+            board: vec![None; size * size],
+            moves_made: 0,
+            groups: UnionFind::new(size * size + 4),
+            winner: None,
+            hash: 0,
+        }
+    }
+
+    /// Enables the swap rule, which lets blue take over red's first stone as
+    /// their first move.
+    pub fn with_swap_rule(mut self) -> Self {
+        self.swap_rule = true;
+        self
+    }
+
+    /// Returns the number of rows & columns on the board.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns whether the swap rule is enabled.
+    pub fn swap_rule(&self) -> bool {
+        self.swap_rule
+    }
+
+    /// Returns the stone in the given cell, where row 0 is the top row.
+    pub fn get_cell(&self, col: usize, row: usize) -> Option<Player> {
+        self.board[self.cell_index(col, row)]
+    }
+
+    /// Returns the index of the given cell in the board.
+    fn cell_index(&self, col: usize, row: usize) -> usize {
+        row * self.size + col
+    }
+
+    /// Returns whether the current player can swap instead of placing a stone.
+    fn can_swap(&self) -> bool {
+        self.swap_rule && self.moves_made == 1
+    }
+
+    /// Returns the nodes of the two edges that the given player connects.
+    fn edges(&self, player: Player) -> (usize, usize) {
+        let cells = self.size * self.size;
+        match player {
+            Player::Red => (cells, cells + 1),
+            Player::Blue => (cells + 2, cells + 3),
+        }
+    }
+
+    /// Places a stone of the given player in the given cell, joining it to the
+    /// player's neighbouring stones & any edge it touches. Returns whether the
+    /// stone connects the player's edges.
+    fn place(&mut self, col: usize, row: usize, player: Player) -> bool {
+        let cell = self.cell_index(col, row);
+        self.board[cell] = Some(player);
+        self.hash ^= zobrist_piece_key(cell, player.index());
+
+        let (first_edge, second_edge) = self.edges(player);
+        let (on_first, on_second) = match player {
+            Player::Red => (row == 0, row + 1 == self.size),
+            Player::Blue => (col == 0, col + 1 == self.size),
+        };
+        if on_first {
+            self.groups.union(cell, first_edge);
+        }
+        if on_second {
+            self.groups.union(cell, second_edge);
+        }
+
+        let (col, row) = (col as i64, row as i64);
+        for &(col_d, row_d) in [(-1, 0), (1, 0), (0, -1), (1, -1), (-1, 1), (0, 1)].iter() {
+            let (c, r) = (col + col_d, row + row_d);
+            if c < 0 || c >= self.size as i64 || r < 0 || r >= self.size as i64 {
+                continue;
+            }
+            let next = self.cell_index(c as usize, r as usize);
+            if self.board[next] == Some(player) {
+                self.groups.union(cell, next);
+            }
+        }
+
+        self.groups.find(first_edge) == self.groups.find(second_edge)
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GameState<Player, Move, MoveError> for Game {
+    fn make_move(&mut self, mv: Move) -> Result<(), MoveError> {
+        match mv {
+            Move::Place { col, row } => {
+                let (col, row) = (col as usize, row as usize);
+                if col >= self.size || row >= self.size {
+                    return Err(MoveError::OutOfRange(mv));
+                }
+                if self.get_cell(col, row).is_some() {
+                    return Err(MoveError::Occupied(mv));
+                }
+
+                if self.place(col, row, self.turn) {
+                    self.winner = Some(self.turn);
+                }
+            }
+            Move::Swap => {
+                if !self.can_swap() {
+                    return Err(MoveError::CannotSwap);
+                }
+
+                // Red's only stone is replaced by a blue stone mirrored along
+                // the long diagonal, which gives blue the same position
+                // relative to their edges. The board is started over, since
+                // the union-find can't remove stones.
+                let cell = self.board.iter().position(Option::is_some).unwrap();
+                let (col, row) = (cell % self.size, cell / self.size);
+                self.board[cell] = None;
+                self.hash ^= zobrist_piece_key(cell, Player::Red.index());
+                self.groups = UnionFind::new(self.size * self.size + 4);
+                if self.place(row, col, Player::Blue) {
+                    self.winner = Some(Player::Blue);
+                }
+            }
+        }
+
+        self.moves_made += 1;
+        self.hash ^= ZOBRIST_TURN_KEY;
+        self.turn = self.turn.next();
+        Ok(())
+    }
+
+    fn get_moves(&self) -> Vec<Move> {
+        if self.winner.is_some() {
+            return vec![];
+        }
+
+        let mut moves: Vec<Move> = self
+            .board
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| cell.is_none())
+            .map(|(i, _)| Move::place((i % self.size) as u8, (i / self.size) as u8))
+            .collect();
+        if self.can_swap() {
+            moves.push(Move::Swap);
+        }
+        moves
+    }
+
+    fn get_winner(&self) -> Option<Player> {
+        self.winner
+    }
+
+    fn get_current_player(&self) -> Player {
+        self.turn
+    }
+
+    fn get_prev_player(&self) -> Player {
+        self.turn.prev()
+    }
+
+    fn get_players(&self) -> Vec<Player> {
+        Player::all()
+    }
+
+    fn position_hash(&self) -> Option<u64> {
+        Some(self.hash)
+    }
+}
+
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        board::fmt_board(f, self.size, self.size, true, |col, row| {
+            self.get_cell(col, row)
+        })
+    }
+}
@@ -0,0 +1,23 @@
+//! A Monte Carlo Tree Search implementation that works with any game that
+//! implements the [`GameState`] trait.
+//!
+//! The [`Mcts`] object holds the search tree for a single player, and is kept
+//! in sync with the game by passing it the moves made by each player. Searches
+//! can be made reproducible by constructing it with [`Mcts::with_seed`] or
+//! [`Mcts::with_rng`].
+
+pub mod board;
+pub mod connect4;
+pub mod game;
+pub mod go;
+pub mod hex;
+pub mod mcts;
+pub mod mnk;
+pub mod othello;
+pub mod pig;
+
+pub use game::GameState;
+pub use mcts::{
+    FinalMoveStrategy, Mcts, Parallelism, RolloutPolicy, RolloutResult, SearchLimit,
+    SelectionPolicy, TreePolicy,
+};
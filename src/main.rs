@@ -1,64 +1,796 @@
-extern crate rand;
-mod connect4;
-mod game;
-mod mcts;
-
-use game::GameState;
-use mcts::{Mcts, SelectionPolicy};
+use clap::builder::RangedU64ValueParser;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use mcts_impl::mcts::{ExportOptions, SearchReport};
+use mcts_impl::{connect4, go, hex, mnk, othello, pig};
+use mcts_impl::{GameState, Mcts, Parallelism, SearchLimit, SelectionPolicy};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
 use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::PathBuf;
+use std::process;
+use std::str::FromStr;
 use std::time::Duration;
 
-/// Runs a game where all players are AIs based on MCTS.
-fn do_ai_game<P, M, ME, S>(
-    state: &mut S,
-    players: Vec<P>,
-    compute_limit: Duration,
+/// The time limit of each search when no limit is given, in milliseconds.
+const DEFAULT_TIME_MS: u64 = 1000;
+/// The virtual loss of tree-parallel searches when none is given.
+const DEFAULT_VIRTUAL_LOSS: u64 = 1;
+
+/// Plays & analyses games using Monte Carlo Tree Search.
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+    #[command(flatten)]
+    game: GameArgs,
+    #[command(flatten)]
+    search: SearchArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Plays a game where each player is either a human or an MCTS AI.
+    Play {
+        /// The kind of each player in turn order, separated by commas. Any
+        /// players left over are MCTS AIs.
+        #[arg(long, value_enum, value_delimiter = ',', default_values = ["human", "mcts"])]
+        players: Vec<Seat>,
+    },
+    /// Plays games where every player is an MCTS AI & reports how often each
+    /// player won.
+    Selfplay {
+        /// The number of games to play.
+        #[arg(long, default_value_t = 10)]
+        games: u32,
+    },
+    /// Searches a single position & reports the statistics of the search.
+    Analyse {
+        /// The moves played to reach the position, separated by commas.
+        #[arg(long, value_delimiter = ',')]
+        moves: Vec<String>,
+        /// Writes the search tree to the given file as a Graphviz DOT graph.
+        #[arg(long)]
+        dot: Option<PathBuf>,
+        /// Writes the search tree to the given file as JSON.
+        #[arg(long)]
+        json: Option<PathBuf>,
+        /// The deepest level of the search tree to export.
+        #[arg(long)]
+        export_depth: Option<usize>,
+    },
+    /// Measures the speed of searches from the start of the game.
+    Bench {
+        /// The number of searches to run.
+        #[arg(long, default_value_t = 5)]
+        repeats: u32,
+    },
+}
+
+/// The games that can be played.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum GameKind {
+    Connect4,
+    /// Go on a square board, which is 9x9 by default.
+    Go,
+    /// Hex on a square board, which is 11x11 by default.
+    Hex,
+    /// The m,n,k-game, which is tic-tac-toe by default.
+    #[value(alias = "tic-tac-toe")]
+    Mnk,
+    /// Othello, which is always played on an 8x8 board.
+    #[value(alias = "reversi")]
+    Othello,
+    /// Pig, a dice game played to 100 points by default.
+    Pig,
+}
+
+/// The options used to set up the game.
+#[derive(Args)]
+struct GameArgs {
+    /// The game to play.
+    #[arg(long, global = true, value_enum, default_value_t = GameKind::Connect4)]
+    game: GameKind,
+    /// The number of columns on the board. Defaults to the standard size of
+    /// the game.
+    #[arg(long, global = true)]
+    width: Option<usize>,
+    /// The number of rows on the board. Defaults to the standard size of the
+    /// game.
+    #[arg(long, global = true)]
+    height: Option<usize>,
+    /// The length of line needed to win in connect 4 & the m,n,k-game.
+    /// Defaults to the standard length of the game.
+    #[arg(long = "connect", global = true)]
+    connect_len: Option<usize>,
+    /// The komi given to white in Go. Defaults to 7.5.
+    #[arg(long, global = true, allow_negative_numbers = true, value_parser = parse_finite)]
+    komi: Option<f64>,
+    /// Lets the second player take over the first stone in Hex.
+    #[arg(long, global = true)]
+    swap: bool,
+    /// The score needed to win in Pig. Defaults to 100.
+    #[arg(long, global = true)]
+    target: Option<u32>,
+}
+
+impl GameArgs {
+    /// Returns the width, height & connect length, using the given defaults
+    /// for any that weren't set.
+    fn size_or(&self, width: usize, height: usize, connect_len: usize) -> (usize, usize, usize) {
+        (
+            self.width.unwrap_or(width),
+            self.height.unwrap_or(height),
+            self.connect_len.unwrap_or(connect_len),
+        )
+    }
+
+    /// Returns an error naming the first of the options that was given but
+    /// isn't used by the chosen game.
+    fn check_options(&self) -> Result<(), String> {
+        use GameKind::*;
+        let options: [(&str, bool, &[GameKind]); 6] = [
+            ("width", self.width.is_some(), &[Connect4, Go, Hex, Mnk]),
+            ("height", self.height.is_some(), &[Connect4, Go, Hex, Mnk]),
+            ("connect", self.connect_len.is_some(), &[Connect4, Mnk]),
+            ("komi", self.komi.is_some(), &[Go]),
+            ("swap", self.swap, &[Hex]),
+            ("target", self.target.is_some(), &[Pig]),
+        ];
+        match options
+            .iter()
+            .find(|(_, given, games)| *given && !games.contains(&self.game))
+        {
+            Some((name, _, _)) => Err(format!(
+                "--{} isn't used by {}",
+                name,
+                self.game.to_possible_value().unwrap().get_name()
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the size of a square board, using the given default if neither
+    /// the width nor the height was set. Returns None if they were both set &
+    /// don't match.
+    fn square_size_or(&self, size: usize) -> Option<usize> {
+        match (self.width, self.height) {
+            (Some(width), Some(height)) if width != height => None,
+            (width, height) => Some(width.or(height).unwrap_or(size)),
+        }
+    }
+}
+
+/// The selection policies that can be picked from the command line.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum PolicyKind {
+    Ucb1,
+    Ucb1Tuned,
+    Puct,
+    Thompson,
+}
+
+/// The ways that searches can be split across threads from the command line.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum ParallelismKind {
+    /// Each thread searches its own tree, & the root statistics are merged.
+    Root,
+    /// Every thread searches one shared tree, using virtual loss.
+    Tree,
+}
+
+/// The options used to set up every search.
+#[derive(Args)]
+struct SearchArgs {
+    /// The time limit of each search in milliseconds. Defaults to 1000 if no
+    /// round limit is given either.
+    #[arg(long, global = true)]
+    time_ms: Option<u64>,
+    /// The number of rounds of each search. If a time limit is also given,
+    /// the search stops at whichever is reached first.
+    #[arg(long, global = true, value_parser = RangedU64ValueParser::<u64>::new().range(1..))]
+    rounds: Option<u64>,
+    /// The selection policy used by the search.
+    #[arg(long, global = true, value_enum, default_value_t = PolicyKind::Ucb1)]
+    policy: PolicyKind,
+    /// The exploration constant of UCB1 or PUCT. Defaults to the constant of
+    /// the policy.
+    #[arg(long, global = true, value_parser = parse_explore)]
+    explore: Option<f64>,
+    /// The seed of the first AI's RNG. Each other AI is seeded with the next
+    /// number along. Searches are only reproducible with a round limit.
+    #[arg(long, global = true)]
+    seed: Option<u64>,
+    /// The number of threads each search is split across.
+    #[arg(long, global = true, default_value_t = 1, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    threads: usize,
+    /// How searches are split across threads when there is more than one.
+    #[arg(long, global = true, value_enum, default_value_t = ParallelismKind::Root)]
+    parallelism: ParallelismKind,
+    /// The virtual loss of tree-parallel searches. Defaults to 1.
+    #[arg(long, global = true, value_parser = RangedU64ValueParser::<u64>::new().range(1..))]
+    virtual_loss: Option<u64>,
+}
+
+impl SearchArgs {
+    /// Returns the search limit given by the time & round limits.
+    fn limit(&self) -> SearchLimit {
+        match (self.time_ms, self.rounds) {
+            (Some(ms), Some(rounds)) => {
+                SearchLimit::Time(Duration::from_millis(ms)).or(SearchLimit::Rounds(rounds))
+            }
+            (None, Some(rounds)) => SearchLimit::Rounds(rounds),
+            (time_ms, None) => {
+                SearchLimit::Time(Duration::from_millis(time_ms.unwrap_or(DEFAULT_TIME_MS)))
+            }
+        }
+    }
+
+    /// Returns an error naming the first of the options that was given but
+    /// isn't used by the chosen policy or parallelism.
+    fn check_options(&self) -> Result<(), String> {
+        if self.explore.is_some() && ![PolicyKind::Ucb1, PolicyKind::Puct].contains(&self.policy) {
+            return Err(format!(
+                "--explore isn't used by {}",
+                self.policy.to_possible_value().unwrap().get_name()
+            ));
+        }
+        if self.virtual_loss.is_some() && self.parallelism != ParallelismKind::Tree {
+            return Err("--virtual-loss is only used by tree parallelism".to_owned());
+        }
+        Ok(())
+    }
+
+    /// Returns the parallelism given by the thread count & parallelism
+    /// options, or None if searches run on a single thread.
+    fn parallelism(&self) -> Option<Parallelism> {
+        match (self.threads, self.parallelism) {
+            (1, _) => None,
+            (threads, ParallelismKind::Root) => Some(Parallelism::Root { threads }),
+            (threads, ParallelismKind::Tree) => Some(Parallelism::Tree {
+                threads,
+                virtual_loss: self.virtual_loss.unwrap_or(DEFAULT_VIRTUAL_LOSS),
+            }),
+        }
+    }
+
+    /// Returns the selection policy given by the policy & exploration
+    /// constant.
+    fn selection_pol(&self) -> SelectionPolicy {
+        match self.policy {
+            PolicyKind::Ucb1 => SelectionPolicy::Ucb1(self.explore),
+            PolicyKind::Ucb1Tuned => SelectionPolicy::Ucb1Tuned,
+            PolicyKind::Puct => SelectionPolicy::Puct(self.explore),
+            PolicyKind::Thompson => SelectionPolicy::Thompson,
+        }
+    }
+}
+
+/// The settings shared by every AI, which are built from the search options.
+struct Settings {
+    limit: SearchLimit,
     selection_pol: SelectionPolicy,
-) where
-    P: Copy + PartialEq + ToString + fmt::Debug,
-    M: Copy + PartialEq + fmt::Debug,
-    ME: Copy + fmt::Debug,
-    S: GameState<P, M, ME>,
+    seed: Option<u64>,
+    /// The parallelism of each search, or None to search on a single thread.
+    parallelism: Option<Parallelism>,
+}
+
+impl Settings {
+    /// Constructs the AI for the given player, where the index is used to give
+    /// each AI its own seed.
+    fn new_ai<P, M, ME, S>(&self, player: P, state: &S, index: u64) -> Mcts<P, M, ME, S>
+    where
+        P: Copy + PartialEq + ToString + fmt::Debug,
+        M: Copy + PartialEq + fmt::Debug,
+        ME: Copy + fmt::Debug,
+        S: GameState<P, M, ME>,
+    {
+        match self.seed {
+            Some(seed) => Mcts::with_seed(player, state, seed.wrapping_add(index)),
+            None => Mcts::new(player, state),
+        }
+    }
+
+    /// Constructs the RNG used to decide the chance events of a game, where
+    /// the index is used to give each game its own seed. The seed is kept
+    /// apart from the seeds of the AIs.
+    fn chance_rng(&self, index: u64) -> StdRng {
+        match self.seed {
+            Some(seed) => StdRng::seed_from_u64(!seed.wrapping_add(index)),
+            None => StdRng::from_entropy(),
+        }
+    }
+
+    /// Runs a search with the given AI, splitting it across threads if more
+    /// than one was asked for.
+    fn search<P, M, ME, S>(&self, ai: &mut Mcts<P, M, ME, S>) -> SearchReport<M>
+    where
+        P: Copy + PartialEq + ToString + fmt::Debug + Send + Sync,
+        M: Copy + PartialEq + fmt::Debug + Send + Sync,
+        ME: Copy + fmt::Debug + Send + Sync,
+        S: GameState<P, M, ME> + Send + Sync,
+    {
+        match &self.parallelism {
+            None => ai.select_next_move(&self.limit, &self.selection_pol),
+            Some(parallelism) => {
+                ai.select_next_move_parallel(&self.limit, &self.selection_pol, parallelism)
+            }
+        }
+    }
+}
+
+/// Who makes the moves for a single player in the game.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum Seat {
+    /// A person entering moves at the prompt.
+    Human,
+    /// An AI based on MCTS.
+    #[value(alias = "ai")]
+    Mcts,
+}
+
+/// Runs the given command on a game starting from the given state.
+fn run<P, M, ME, S>(mut state: S, command: &Command, settings: &Settings)
+where
+    P: Copy + PartialEq + ToString + fmt::Debug + Send + Sync,
+    M: Copy + PartialEq + fmt::Debug + FromStr + Send + Sync,
+    ME: Copy + fmt::Debug + Send + Sync,
+    S: GameState<P, M, ME> + Send + Sync,
 {
-    let mut cur_ply = 0;
-    let mut ais: Vec<Mcts<P, M, ME, S>> =
-        players.iter().map(|&ply| Mcts::new(ply, state)).collect();
+    match command {
+        Command::Play { players } => {
+            let seats = state.get_players().len();
+            if players.len() > seats {
+                exit_with(&format!(
+                    "--players gives {} players, but the game only has {}",
+                    players.len(),
+                    seats
+                ));
+            }
+            let players = state
+                .get_players()
+                .into_iter()
+                .enumerate()
+                .map(|(i, ply)| (ply, players.get(i).copied().unwrap_or(Seat::Mcts)))
+                .collect();
+            do_game(&mut state, players, settings);
+        }
+        Command::Selfplay { games } => do_selfplay(&state, *games, settings),
+        Command::Analyse {
+            moves,
+            dot,
+            json,
+            export_depth,
+        } => {
+            for mv in moves {
+                let parsed = match mv.trim().parse::<M>() {
+                    Ok(parsed) => parsed,
+                    Err(_) => exit_with(&format!("'{}' is not a move", mv)),
+                };
+                if let Err(err) = state.make_move(parsed) {
+                    exit_with(&format!("{:?}", err));
+                }
+            }
+
+            // The exported edges are scored with the policy of the search.
+            let mut options = ExportOptions::new().with_policy(settings.selection_pol);
+            if let Some(depth) = export_depth {
+                options = options.with_max_depth(*depth);
+            }
+            do_analyse(&state, settings, dot.as_ref(), json.as_ref(), &options);
+        }
+        Command::Bench { repeats } => do_bench(&state, *repeats, settings),
+    }
+}
+
+/// Runs a game where each player is either a human entering moves at the
+/// prompt or an AI based on MCTS. Each turn is played by the seat of the
+/// current player in the game state, so the game decides the turn order.
+///
+/// Every AI keeps its tree in sync with the game, whoever made the move.
+/// Chance events are decided at random by their probabilities.
+fn do_game<P, M, ME, S>(state: &mut S, players: Vec<(P, Seat)>, settings: &Settings)
+where
+    P: Copy + PartialEq + ToString + fmt::Debug + Send + Sync,
+    M: Copy + PartialEq + fmt::Debug + FromStr + Send + Sync,
+    ME: Copy + fmt::Debug + Send + Sync,
+    S: GameState<P, M, ME> + Send + Sync,
+{
+    let mut ais: Vec<Option<Mcts<P, M, ME, S>>> = players
+        .iter()
+        .enumerate()
+        .map(|(i, &(ply, seat))| match seat {
+            Seat::Human => None,
+            Seat::Mcts => Some(settings.new_ai(ply, state, i as u64)),
+        })
+        .collect();
 
     println!("{}", state);
 
+    let mut rng = settings.chance_rng(0);
     while !state.get_moves().is_empty() {
-        let (mv, rounds) = ais[cur_ply].select_next_move(compute_limit, &selection_pol);
-        state.make_move(mv).unwrap();
+        if let Some(mv) = make_chance_move(state, &mut rng) {
+            println!("Chance outcome: {:?}\n{}", mv, state);
+            for ai in ais.iter_mut().flatten() {
+                ai.update_chance_move(mv);
+            }
+            continue;
+        }
+
+        let cur_ply = players
+            .iter()
+            .position(|&(ply, _)| ply == state.get_current_player())
+            .unwrap();
+        let mv = match &mut ais[cur_ply] {
+            Some(ai) => {
+                let report = settings.search(ai);
+                state.make_move(report.best_move).unwrap();
+                println!("{}\n{}", state, report);
+                report.best_move
+            }
+            None => match prompt_move(state) {
+                Some(mv) => {
+                    println!("{}", state);
+                    mv
+                }
+                None => {
+                    println!("Input closed, ending game");
+                    return;
+                }
+            },
+        };
 
         for (i, ai) in ais.iter_mut().enumerate() {
-            if i == cur_ply {
-                ai.update_target_move(mv);
-            } else {
-                ai.update_opponent_move(mv);
+            match ai {
+                Some(ai) if i == cur_ply => ai.update_target_move(mv),
+                Some(ai) => ai.update_opponent_move(mv),
+                None => {}
+            }
+        }
+    }
+
+    println!("Game ended, winner: {}", winner_name(state));
+}
+
+/// Asks the current player for a move until they enter one that can be made,
+/// & makes it on the given state. Returns None if the input is closed first.
+fn prompt_move<P, M, ME, S>(state: &mut S) -> Option<M>
+where
+    P: Copy + PartialEq + ToString + fmt::Debug,
+    M: Copy + PartialEq + fmt::Debug + FromStr,
+    ME: Copy + fmt::Debug,
+    S: GameState<P, M, ME>,
+{
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!(
+            "{} to move, available moves {:?}: ",
+            state.get_current_player().to_string(),
+            state.get_moves()
+        );
+        io::stdout().flush().unwrap();
+
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            _ => return None,
+        };
+        let mv = match line.trim().parse::<M>() {
+            Ok(mv) => mv,
+            Err(_) => {
+                println!("'{}' is not a move", line.trim());
+                continue;
             }
+        };
+
+        // The move errors of each game describe what was wrong with the move.
+        match state.make_move(mv) {
+            Ok(()) => return Some(mv),
+            Err(err) => println!("{:?}", err),
         }
+    }
+}
+
+/// Plays the given number of games between MCTS AIs from the given state, &
+/// prints the result of each game along with how often each player won.
+/// Chance events are decided at random by their probabilities & don't count
+/// as moves.
+fn do_selfplay<P, M, ME, S>(start: &S, games: u32, settings: &Settings)
+where
+    P: Copy + PartialEq + ToString + fmt::Debug + Send + Sync,
+    M: Copy + PartialEq + fmt::Debug + Send + Sync,
+    ME: Copy + fmt::Debug + Send + Sync,
+    S: GameState<P, M, ME> + Send + Sync,
+{
+    let players = start.get_players();
+    let mut results: HashMap<String, u32> = HashMap::new();
 
-        println!("{}\n{} rounds of MCTS", state, rounds);
+    for game in 0..games {
+        let mut state = start.clone();
+        let mut ais: Vec<Mcts<P, M, ME, S>> = players
+            .iter()
+            .enumerate()
+            .map(|(i, &ply)| {
+                settings.new_ai(ply, &state, (game as usize * players.len() + i) as u64)
+            })
+            .collect();
 
-        cur_ply += 1;
-        cur_ply %= ais.len();
+        let mut rng = settings.chance_rng(game as u64);
+        let mut moves = 0;
+        while !state.get_moves().is_empty() {
+            if let Some(mv) = make_chance_move(&mut state, &mut rng) {
+                for ai in ais.iter_mut() {
+                    ai.update_chance_move(mv);
+                }
+                continue;
+            }
+
+            let cur_ply = players
+                .iter()
+                .position(|&ply| ply == state.get_current_player())
+                .unwrap();
+            let mv = settings.search(&mut ais[cur_ply]).best_move;
+            state.make_move(mv).unwrap();
+            moves += 1;
+
+            for (i, ai) in ais.iter_mut().enumerate() {
+                if i == cur_ply {
+                    ai.update_target_move(mv);
+                } else {
+                    ai.update_opponent_move(mv);
+                }
+            }
+        }
+
+        let winner = winner_name(&state);
+        println!("Game {}: winner {} after {} moves", game + 1, winner, moves);
+        *results.entry(winner).or_insert(0) += 1;
     }
 
-    println!(
-        "Game ended, winner: {}",
-        match state.get_winner() {
-            Some(ply) => ply.to_string(),
-            None => "None".to_owned(),
+    let mut results: Vec<_> = results.into_iter().collect();
+    results.sort();
+    println!("Results over {} games:", games);
+    for (winner, count) in results {
+        println!("  {}: {}", winner, count);
+    }
+}
+
+/// Searches the given state & prints the report, optionally exporting the
+/// search tree to the given files.
+fn do_analyse<P, M, ME, S>(
+    state: &S,
+    settings: &Settings,
+    dot: Option<&PathBuf>,
+    json: Option<&PathBuf>,
+    options: &ExportOptions,
+) where
+    P: Copy + PartialEq + ToString + fmt::Debug + Send + Sync,
+    M: Copy + PartialEq + fmt::Debug + Send + Sync,
+    ME: Copy + fmt::Debug + Send + Sync,
+    S: GameState<P, M, ME> + Send + Sync,
+{
+    println!("{}", state);
+    if state.get_moves().is_empty() {
+        println!("Game has ended, winner: {}", winner_name(state));
+        return;
+    }
+    if let Some(outcomes) = state.get_chance_outcomes() {
+        println!("Waiting on a chance event, outcomes: {:?}", outcomes);
+        return;
+    }
+
+    let mut ai = settings.new_ai(state.get_current_player(), state, 0);
+    println!("{}", settings.search(&mut ai));
+
+    let export = |path: &PathBuf, write: &dyn Fn(&mut BufWriter<File>) -> io::Result<()>| {
+        let result = File::create(path).and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            write(&mut writer)?;
+            writer.flush()
+        });
+        if let Err(err) = result {
+            exit_with(&format!("Failed to write {}: {}", path.display(), err));
         }
-    );
+    };
+    if let Some(path) = dot {
+        export(path, &|writer| ai.export_dot(writer, options));
+    }
+    if let Some(path) = json {
+        export(path, &|writer| ai.export_json(writer, options));
+    }
+}
+
+/// Runs the given number of searches from the given state & prints the speed
+/// of each, along with the average.
+fn do_bench<P, M, ME, S>(state: &S, repeats: u32, settings: &Settings)
+where
+    P: Copy + PartialEq + ToString + fmt::Debug + Send + Sync,
+    M: Copy + PartialEq + fmt::Debug + Send + Sync,
+    ME: Copy + fmt::Debug + Send + Sync,
+    S: GameState<P, M, ME> + Send + Sync,
+{
+    let mut total_rounds = 0;
+    let mut total_time = Duration::default();
+    for i in 0..repeats {
+        let mut ai = settings.new_ai(state.get_current_player(), state, i as u64);
+        let report = settings.search(&mut ai);
+        println!(
+            "Search {}: {} rounds in {:.3}s ({:.0} rounds/s), {} nodes",
+            i + 1,
+            report.rounds,
+            report.elapsed.as_secs_f64(),
+            report.rounds_per_sec(),
+            report.tree_size
+        );
+        total_rounds += report.rounds;
+        total_time += report.elapsed;
+    }
+
+    if repeats > 0 {
+        println!(
+            "Average: {:.0} rounds/s",
+            total_rounds as f64 / total_time.as_secs_f64()
+        );
+    }
+}
+
+// Util fns.
+
+/// Makes a move for the chance event of the given state by sampling its
+/// outcomes, & returns the move. Returns None if a player moves next.
+fn make_chance_move<P, M, ME, S, R>(state: &mut S, rng: &mut R) -> Option<M>
+where
+    P: Copy + PartialEq + ToString + fmt::Debug,
+    M: Copy + PartialEq + fmt::Debug,
+    ME: Copy + fmt::Debug,
+    S: GameState<P, M, ME>,
+    R: Rng,
+{
+    let outcomes = state.get_chance_outcomes()?;
+    let &(mv, _) = outcomes
+        .choose_weighted(rng, |&(_, probability)| probability)
+        .unwrap();
+    state.make_move(mv).unwrap();
+    Some(mv)
+}
+
+/// Returns the name of the winner of the given state, or "None" if there isn't
+/// one.
+fn winner_name<P, M, ME, S>(state: &S) -> String
+where
+    P: Copy + PartialEq + ToString + fmt::Debug,
+    M: Copy + PartialEq + fmt::Debug,
+    ME: Copy + fmt::Debug,
+    S: GameState<P, M, ME>,
+{
+    match state.get_winner() {
+        Some(ply) => ply.to_string(),
+        None => "None".to_owned(),
+    }
+}
+
+/// Parses a number that must be finite.
+fn parse_finite(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(value) if value.is_finite() => Ok(value),
+        Ok(_) => Err("must be a finite number".to_owned()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Parses an exploration constant, which must be finite & non-negative.
+fn parse_explore(s: &str) -> Result<f64, String> {
+    match parse_finite(s)? {
+        value if value >= 0.0 => Ok(value),
+        _ => Err("must not be negative".to_owned()),
+    }
+}
+
+/// Prints the given error message & exits with a failure code.
+fn exit_with(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1)
 }
 
 fn main() {
-    let mut state = connect4::Game::new();
-    do_ai_game(
-        &mut state,
-        connect4::Player::all(),
-        Duration::from_millis(1000),
-        SelectionPolicy::Ucb1(None),
-    );
+    let cli = Cli::parse();
+    let settings = Settings {
+        limit: cli.search.limit(),
+        selection_pol: cli.search.selection_pol(),
+        seed: cli.search.seed,
+        parallelism: cli.search.parallelism(),
+    };
+    if let Err(message) = cli.game.check_options().and(cli.search.check_options()) {
+        exit_with(&message);
+    }
+
+    match cli.game.game {
+        GameKind::Connect4 => {
+            let (width, height, connect_len) = cli.game.size_or(
+                connect4::DEFAULT_WIDTH,
+                connect4::DEFAULT_HEIGHT,
+                connect4::DEFAULT_CONNECT_LEN,
+            );
+            if width == 0 || height == 0 || width > 256 || connect_len == 0 {
+                exit_with("Connect 4 needs 1-256 columns, at least 1 row & a connect length of at least 1");
+            }
+            if width.checked_mul(height).is_none() {
+                exit_with("The connect 4 board has too many cells");
+            }
+
+            // The bitboard is much faster, but only fits smaller boards.
+            if connect4::bitboard::Game::fits(width, height) {
+                run(
+                    connect4::bitboard::Game::with_size(width, height, connect_len),
+                    &cli.command,
+                    &settings,
+                );
+            } else {
+                run(
+                    connect4::Game::with_size(width, height, connect_len),
+                    &cli.command,
+                    &settings,
+                );
+            }
+        }
+        GameKind::Go => {
+            let size = match cli.game.square_size_or(go::DEFAULT_SIZE) {
+                Some(size) => size,
+                None => exit_with("Go is only played on square boards"),
+            };
+            if size == 0 || size > go::MAX_SIZE {
+                exit_with("Go needs a board size of 1-19");
+            }
+
+            run(
+                go::Game::with_size(size, cli.game.komi.unwrap_or(go::DEFAULT_KOMI)),
+                &cli.command,
+                &settings,
+            );
+        }
+        GameKind::Hex => {
+            let size = match cli.game.square_size_or(hex::DEFAULT_SIZE) {
+                Some(size) => size,
+                None => exit_with("Hex is only played on square boards"),
+            };
+            if size == 0 || size > hex::MAX_SIZE {
+                exit_with("Hex needs a board size of 1-26");
+            }
+
+            let mut game = hex::Game::with_size(size);
+            if cli.game.swap {
+                game = game.with_swap_rule();
+            }
+            run(game, &cli.command, &settings);
+        }
+        GameKind::Mnk => {
+            let (width, height, connect_len) = cli.game.size_or(
+                mnk::TIC_TAC_TOE_SIZE,
+                mnk::TIC_TAC_TOE_SIZE,
+                mnk::TIC_TAC_TOE_SIZE,
+            );
+            if width == 0 || height == 0 || width > 26 || height > 255 || connect_len == 0 {
+                exit_with("The m,n,k-game needs 1-26 columns, 1-255 rows & a connect length of at least 1");
+            }
+
+            run(
+                mnk::Game::with_size(width, height, connect_len),
+                &cli.command,
+                &settings,
+            );
+        }
+        GameKind::Othello => run(othello::Game::new(), &cli.command, &settings),
+        GameKind::Pig => {
+            let target = cli.game.target.unwrap_or(pig::DEFAULT_TARGET);
+            if target == 0 {
+                exit_with("Pig needs a target score of at least 1");
+            }
+
+            run(pig::Game::with_target(target), &cli.command, &settings);
+        }
+    }
 }
@@ -1,38 +1,95 @@
+mod export;
+mod final_move;
+mod limit;
+mod parallel;
+#[cfg(feature = "persist")]
+mod persist;
+mod report;
+mod rollout;
+mod selection;
+
+pub use self::export::ExportOptions;
+pub use self::final_move::{FinalMoveStrategy, SECURE_DEFAULT_CONST};
+pub use self::limit::SearchLimit;
+pub use self::parallel::Parallelism;
+#[cfg(feature = "persist")]
+pub use self::persist::{PersistError, FORMAT_VERSION};
+pub use self::report::{ChildReport, SearchReport};
+pub use self::rollout::{DepthLimited, EpsilonGreedy, RolloutPolicy, RolloutResult, UniformRandom};
+pub use self::selection::{
+    SelectionPolicy, TreePolicy, PUCT_DEFAULT_EXPLORE_CONST, UCB1_DEFAULT_EXPLORE_CONST,
+};
+
 use crate::game::GameState;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use std::f64::consts::SQRT_2;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
 use std::fmt;
 use std::marker::PhantomData;
-use std::time::{Duration, Instant};
+use std::sync::Arc;
+use std::time::Instant;
 
-// Default UBC1 exploration constant. Equals sqrt(2).
-pub const UCB1_DEFAULT_EXPLORE_CONST: f64 = SQRT_2;
+/// The default reward given to every player when a game ends in a draw.
+pub const DEFAULT_DRAW_VALUE: f64 = 0.5;
 
-pub enum SelectionPolicy {
-    Ucb1(Option<f64>),
-}
+/// A function that returns the prior probability of a move being played from a
+/// given state.
+pub type PriorFn<M, S> = dyn Fn(&S, M) -> f64 + Send + Sync;
 
+/// A single node in the search tree. Nodes can be inspected by custom
+/// [`TreePolicy`] implementations, but are only modified by [`Mcts`].
 #[derive(Clone)]
-struct Node<P, M, ME, S>
+#[cfg_attr(
+    feature = "persist",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "P: serde::Serialize, M: serde::Serialize, S: serde::Serialize",
+        deserialize = "P: serde::Deserialize<'de>, M: serde::Deserialize<'de>, \
+                       S: serde::Deserialize<'de>"
+    ))
+)]
+pub struct Node<P, M, ME, S>
 where
     P: Copy + PartialEq + ToString + fmt::Debug,
     M: Copy + PartialEq + fmt::Debug,
     ME: Copy + fmt::Debug,
     S: GameState<P, M, ME>,
 {
-    /// The move that got the game state to this node.
+    /// The move that got the game state to this node from the parent that it
+    /// was first created from.
     mv: Option<M>,
-    /// The ID of the parent node, or None if this is the root node.
-    parent_node: Option<usize>,
-    /// The IDs of the child nodes.
-    child_nodes: Vec<usize>,
-    /// The number of wins the player just moved has from this node.
-    /// Specifically, the previous player in the game state is used.
-    wins: u64,
+    /// The player that made the move that got the game state to this node.
+    /// For the root node of the whole tree, the previous player is used.
+    player: P,
+    /// The IDs of the parent nodes, which is empty for the root node. Only
+    /// nodes shared through the transposition table have more than one parent.
+    parent_nodes: Vec<usize>,
+    /// The child nodes, along with the move that leads to each of them.
+    child_nodes: Vec<(M, usize)>,
+    /// The total reward the player just moved has from this node.
+    /// Specifically, the player that moved into this node is used. Wins add 1,
+    /// draws add the draw value & rollouts that were cut short add their value.
+    wins: f64,
+    /// The sum of the squares of each reward added to `wins`.
+    wins_sq: f64,
+    /// The total reward of every player in the game from this node.
+    player_rewards: Vec<(P, f64)>,
     /// The number of times this node has been rolled out from.
     visits: u64,
+    /// The prior probability of the move that got the game state to this node.
+    prior: f64,
     /// The vec of untried moves that are still available.
     untried_mvs: Vec<M>,
+    /// The outcomes of the chance event that happens next, along with the
+    /// probability of each, or None if a player moves next. The children of a
+    /// chance node are sampled by probability during selection instead of
+    /// using the tree policy.
+    outcomes: Option<Vec<(M, f64)>>,
+    /// The result of the game from this node under perfect play, once it has
+    /// been proven. Terminal nodes are proven as soon as they are created, and
+    /// other nodes are proven from their children during backprop.
+    proven: Option<RolloutResult<P>>,
     /// The game state that this node reflects.
     state: S, // TODO [mem]: Move to Option<Box> & drop once done with.
     // Required members due to odd generic params.
@@ -48,20 +105,91 @@ where
     S: GameState<P, M, ME>,
 {
     /// Constructs a new node using the given setup data.
-    pub fn new(mv: Option<M>, parent_node: Option<usize>, state: S) -> Self {
+    fn new(mv: Option<M>, player: P, parent_node: Option<usize>, state: S, prior: f64) -> Self {
+        let moves = state.get_moves();
+        let proven = if moves.is_empty() {
+            Some(RolloutResult::from_terminal(&state))
+        } else {
+            None
+        };
+
         Node {
             mv,
-            parent_node,
+            player,
+            parent_nodes: parent_node.into_iter().collect(),
             child_nodes: vec![],
-            wins: 0,
+            wins: 0.0,
+            wins_sq: 0.0,
+            player_rewards: state
+                .get_players()
+                .into_iter()
+                .map(|ply| (ply, 0.0))
+                .collect(),
             visits: 0,
-            untried_mvs: state.get_moves(),
+            prior,
+            untried_mvs: moves,
+            outcomes: state.get_chance_outcomes(),
+            proven,
             state,
             _phantom_p: PhantomData,
             _phantom_me: PhantomData,
         }
     }
 
+    /// Returns the move that got the game state to this node from the parent
+    /// that it was first created from, or None if this is the root node of the
+    /// whole tree.
+    pub fn mv(&self) -> Option<M> {
+        self.mv
+    }
+
+    /// Returns the player that made the move that got the game state to this
+    /// node.
+    pub fn player(&self) -> P {
+        self.player
+    }
+
+    /// Returns the total reward of the given player from this node.
+    pub fn reward_of(&self, player: P) -> f64 {
+        self.player_rewards
+            .iter()
+            .find(|(ply, _)| *ply == player)
+            .map_or(0.0, |&(_, reward)| reward)
+    }
+
+    /// Returns the total reward the player that moved into this node has
+    /// received from it.
+    pub fn wins(&self) -> f64 {
+        self.wins
+    }
+
+    /// Returns the sum of the squares of the rewards the player that moved
+    /// into this node has received from it.
+    pub fn wins_sq(&self) -> f64 {
+        self.wins_sq
+    }
+
+    /// Returns the number of times this node has been visited.
+    pub fn visits(&self) -> u64 {
+        self.visits
+    }
+
+    /// Returns the average reward of this node.
+    pub fn win_rate(&self) -> f64 {
+        self.wins / (self.visits as f64)
+    }
+
+    /// Returns the prior probability of the move that got the game state to
+    /// this node.
+    pub fn prior(&self) -> f64 {
+        self.prior
+    }
+
+    /// Returns the game state that this node reflects.
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
     /// Returns whether this node is fully expanded or not.
     /// If false, then more children can be added.
     pub fn is_fully_expanded(&self) -> bool {
@@ -73,24 +201,78 @@ where
         !self.child_nodes.is_empty()
     }
 
-    /// Updates the visits & wins counts based on the given winner.
-    pub fn update(&mut self, winner: Option<P>) {
+    /// Returns whether this node is a chance event, whose children are the
+    /// outcomes of the event rather than moves made by a player.
+    pub fn is_chance(&self) -> bool {
+        self.outcomes.is_some()
+    }
+
+    /// Returns the result of the game from this node under perfect play, or
+    /// None if it hasn't been proven yet.
+    pub fn proven(&self) -> Option<&RolloutResult<P>> {
+        self.proven.as_ref()
+    }
+
+    /// Returns whether the result of the game from this node has been proven.
+    pub fn is_proven(&self) -> bool {
+        self.proven.is_some()
+    }
+
+    /// Returns whether this node is proven to be won by the given player.
+    fn is_proven_win_for(&self, player: P) -> bool {
+        matches!(self.proven, Some(RolloutResult::Winner(Some(wnr))) if wnr == player)
+    }
+
+    /// Returns whether this node is proven to be won by a player other than
+    /// the given player.
+    fn is_proven_loss_for(&self, player: P) -> bool {
+        matches!(self.proven, Some(RolloutResult::Winner(Some(wnr))) if wnr != player)
+    }
+
+    /// Updates the visits & reward counts of every player based on the given
+    /// rollout result. A win is worth 1, a loss 0 and a draw the given draw
+    /// value.
+    fn update(&mut self, result: &RolloutResult<P>, draw_value: f64) {
+        for (ply, total) in self.player_rewards.iter_mut() {
+            *total += result.reward_for(*ply, draw_value);
+        }
+
+        let reward = result.reward_for(self.player, draw_value);
         self.visits += 1;
+        self.wins += reward;
+        self.wins_sq += reward * reward;
+    }
 
-        if let Some(wnr) = winner {
-            if wnr == self.state.get_prev_player() {
-                self.wins += 1;
-            }
+    /// Adds the visits & wins counts of another node that represents the same
+    /// game state onto this node. If the other node started out as a copy of
+    /// the given base node, only the counts it gained since then are added.
+    fn merge_stats(&mut self, other: &Self, base: Option<&Self>) {
+        for (ply, total) in self.player_rewards.iter_mut() {
+            *total += other.reward_of(*ply) - base.map_or(0.0, |base| base.reward_of(*ply));
+        }
+
+        self.visits += other.visits - base.map_or(0, |base| base.visits);
+        self.wins += other.wins - base.map_or(0.0, |base| base.wins);
+        self.wins_sq += other.wins_sq - base.map_or(0.0, |base| base.wins_sq);
+        if self.proven.is_none() {
+            self.proven = other.proven.clone();
         }
     }
 }
 
-pub struct Mcts<P, M, ME, S>
+/// The MCTS search tree for a single player. The tree is kept between moves,
+/// so it needs to be told about every move that is made in the game.
+///
+/// All randomness in the search comes from the RNG `R`, so a seeded RNG
+/// together with the same state & number of rounds will always build the same
+/// tree & select the same move.
+pub struct Mcts<P, M, ME, S, R = StdRng>
 where
     P: Copy + PartialEq + ToString + fmt::Debug,
     M: Copy + PartialEq + fmt::Debug,
     ME: Copy + fmt::Debug,
     S: GameState<P, M, ME>,
+    R: Rng,
 {
     /// The node tree.
     tree: Vec<Node<P, M, ME, S>>,
@@ -98,90 +280,238 @@ where
     cur_node_id: usize,
     /// The player that we are working for. This is mostly for checking purposes.
     target_player: P,
+    /// The RNG used for expansion & rollouts.
+    rng: R,
+    /// The policy used to play out games during the rollout phase.
+    rollout_pol: Arc<dyn RolloutPolicy<P, M, ME, S> + Send + Sync>,
+    /// The function used to set the prior of each new node, or None to give
+    /// every move from a node the same prior.
+    prior_fn: Option<Arc<PriorFn<M, S>>>,
+    /// The strategy used to pick the move to play once the search has finished.
+    final_move: FinalMoveStrategy,
+    /// The reward given to every player when a game ends in a draw.
+    draw_value: f64,
+    /// The table mapping position hashes to the node representing them, or
+    /// None if transpositions are disabled.
+    transpositions: Option<HashMap<u64, usize>>,
 }
 
-impl<P, M, ME, S> Mcts<P, M, ME, S>
+impl<P, M, ME, S> Mcts<P, M, ME, S, StdRng>
 where
     P: Copy + PartialEq + ToString + fmt::Debug,
     M: Copy + PartialEq + fmt::Debug,
     ME: Copy + fmt::Debug,
     S: GameState<P, M, ME>,
 {
-    /// Constructs a new Mcts object given the player and initial state.
+    /// Constructs a new Mcts object given the player and initial state. The
+    /// RNG is seeded from system entropy, so the search is not reproducible.
     pub fn new(target_player: P, orig_state: &S) -> Self {
+        Self::with_rng(target_player, orig_state, StdRng::from_entropy())
+    }
+
+    /// Constructs a new Mcts object given the player, initial state and the
+    /// seed to use for the RNG.
+    pub fn with_seed(target_player: P, orig_state: &S, seed: u64) -> Self {
+        Self::with_rng(target_player, orig_state, StdRng::seed_from_u64(seed))
+    }
+}
+
+impl<P, M, ME, S, R> Mcts<P, M, ME, S, R>
+where
+    P: Copy + PartialEq + ToString + fmt::Debug,
+    M: Copy + PartialEq + fmt::Debug,
+    ME: Copy + fmt::Debug,
+    S: GameState<P, M, ME>,
+    R: Rng,
+{
+    /// Constructs a new Mcts object given the player, initial state and the
+    /// RNG to use for the search.
+    pub fn with_rng(target_player: P, orig_state: &S, rng: R) -> Self {
         let mut mcts = Mcts {
             tree: vec![],
             cur_node_id: Default::default(),
             target_player,
+            rng,
+            rollout_pol: Arc::new(UniformRandom),
+            prior_fn: None,
+            final_move: FinalMoveStrategy::default(),
+            draw_value: DEFAULT_DRAW_VALUE,
+            transpositions: None,
         };
-        mcts.cur_node_id = mcts.push_node(Node::new(None, None, orig_state.clone()));
+        mcts.cur_node_id = mcts.push_node(Node::new(
+            None,
+            orig_state.get_prev_player(),
+            None,
+            orig_state.clone(),
+            1.0,
+        ));
         mcts
     }
 
+    /// Sets the policy used to play out games during the rollout phase. By
+    /// default, uniformly random moves are played until the game ends.
+    pub fn with_rollout_policy<RP>(mut self, rollout_pol: RP) -> Self
+    where
+        RP: RolloutPolicy<P, M, ME, S> + Send + Sync + 'static,
+    {
+        self.rollout_pol = Arc::new(rollout_pol);
+        self
+    }
+
+    /// Sets the function used to give each move a prior probability, which is
+    /// used by [`SelectionPolicy::Puct`]. The function is given the state
+    /// before the move & the move itself. By default, every move from a node
+    /// has the same prior.
+    pub fn with_priors<F>(mut self, prior_fn: F) -> Self
+    where
+        F: Fn(&S, M) -> f64 + Send + Sync + 'static,
+    {
+        self.prior_fn = Some(Arc::new(prior_fn));
+        self
+    }
+
+    /// Sets the strategy used to pick the move to play once the search has
+    /// finished. By default, the child with the highest win rate is played.
+    pub fn with_final_move_strategy(mut self, final_move: FinalMoveStrategy) -> Self {
+        self.final_move = final_move;
+        self
+    }
+
+    /// Sets the reward given to every player when a game ends in a draw, where a
+    /// win is worth 1 & a loss 0. Defaults to [`DEFAULT_DRAW_VALUE`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the draw value isn't in `[0, 1]`.
+    pub fn with_draw_value(mut self, draw_value: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&draw_value),
+            "Draw value must be in [0, 1], but was {}",
+            draw_value
+        );
+        self.draw_value = draw_value;
+        self
+    }
+
+    /// Enables the transposition table, which shares a single node between
+    /// every path that reaches the same position, turning the tree into a DAG.
+    /// Positions are keyed on [`GameState::position_hash`], and states without
+    /// a hash are never shared.
+    ///
+    /// The hash must capture everything that affects the rest of the game, and
+    /// the game must never repeat a position.
+    pub fn with_transpositions(mut self) -> Self {
+        self.transpositions = Some(HashMap::new());
+        self.rebuild_transpositions();
+        self
+    }
+
     /// Updates the root node to reflect an opponent's move.
     pub fn update_opponent_move(&mut self, mv: M) {
-        self.update_move(mv, false);
+        self.update_move(mv, Some(false));
     }
 
     /// Updates the root node to reflect the target player's move.
     pub fn update_target_move(&mut self, mv: M) {
-        self.update_move(mv, true);
+        self.update_move(mv, Some(true));
+    }
+
+    /// Updates the root node to reflect the outcome of a chance event, such as
+    /// a die roll.
+    pub fn update_chance_move(&mut self, mv: M) {
+        self.update_move(mv, None);
     }
 
-    /// Runs MCTS to select the next best move until the compute limit is reached.
-    /// Once this limit is reached, the best move is selected & returned, along
-    /// with the number of rounds that were performed within the limit.
-    pub fn select_next_move(
-        &mut self,
-        compute_limit: Duration,
-        selection_pol: &SelectionPolicy,
-    ) -> (M, u64) {
+    /// Runs MCTS to select the next best move until the search limit is reached.
+    /// Once this limit is reached, the best move is selected using the final
+    /// move strategy & returned as part of a report on the search.
+    ///
+    /// At least one round is always performed, so that there is a move to
+    /// select.
+    ///
+    /// The selection policy can be any [`TreePolicy`], which includes each of
+    /// the built-in [`SelectionPolicy`] variants.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the game has ended or a chance event is pending, since there
+    /// is no move to select.
+    pub fn select_next_move<T>(&mut self, limit: &SearchLimit, selection_pol: &T) -> SearchReport<M>
+    where
+        T: TreePolicy<P, M, ME, S> + ?Sized,
+    {
+        self.assert_can_select();
         // Prune out nodes we don't need.
         self.prune_nodes();
 
+        let start = Instant::now();
+        let rounds = self.search(limit, selection_pol);
+        self.build_report(self.phase_action_select(), rounds, start.elapsed())
+    }
+
+    // General helper fns.
+
+    /// Panics if there is no move to select from the current root node.
+    fn assert_can_select(&self) {
+        let root = self.get_cur_node();
+        assert!(
+            !root.state.get_moves().is_empty(),
+            "Selecting move but the game has ended"
+        );
+        assert!(!root.is_chance(), "Selecting move but on a chance event");
+    }
+
+    /// Performs rounds of MCTS from the current root node until the search
+    /// limit is reached or the result of the game from the root is proven, &
+    /// returns the number of rounds performed.
+    fn search<T>(&mut self, limit: &SearchLimit, selection_pol: &T) -> u64
+    where
+        T: TreePolicy<P, M, ME, S> + ?Sized,
+    {
         let start = Instant::now();
         let mut rounds = 0;
-        while Instant::now() - start < compute_limit {
-            let mut node = self.phase_selection(self.cur_node_id, selection_pol);
-            node = self.phase_expansion(node);
-            let winner = self.phase_rollout(&self.get_node(node).state);
-            self.phase_backprop(node, winner);
+        while rounds == 0
+            || !(limit.is_reached(rounds, self.tree.len(), start.elapsed())
+                || self.get_cur_node().is_proven())
+        {
+            let mut path = self.phase_selection(self.cur_node_id, selection_pol);
+            self.phase_expansion(&mut path);
+            let result = self.phase_rollout(*path.last().unwrap());
+            self.phase_backprop(&path, &result);
             rounds += 1;
         }
 
-        (self.phase_action_select(), rounds)
+        rounds
     }
 
-    // General helper fns.
-
-    /// Updates the root node to match to move that was performed. Does some
+    /// Updates the root node to match to move that was performed, where None
+    /// means that the move is the outcome of a chance event. Does some
     /// quality-of-life checks to ensure we are working with the right player.
-    fn update_move(&mut self, mv: M, for_target_player: bool) {
+    fn update_move(&mut self, mv: M, for_target_player: Option<bool>) {
         let tgt = self.target_player;
         let node = self.get_cur_node();
 
         // Ensure that we are working with the right player.
         let target_is_current = tgt == node.state.get_current_player();
-        if for_target_player && !target_is_current {
-            panic!("Updating move for target player but on opponent");
-        } else if !for_target_player && target_is_current {
-            panic!("Updating move for opponent but on target player");
+        match for_target_player {
+            None if !node.is_chance() => panic!("Updating chance outcome but on a player's move"),
+            Some(_) if node.is_chance() => panic!("Updating player's move but on a chance event"),
+            Some(true) if !target_is_current => {
+                panic!("Updating move for target player but on opponent")
+            }
+            Some(false) if target_is_current => {
+                panic!("Updating move for opponent but on target player")
+            }
+            _ => {}
         }
 
         // Attempt to find a child node from the root that matches the move that
         // has been performed.
-        let mut next_id: Option<usize> = None;
-        for (id, child) in node
+        let next_id = node
             .child_nodes
             .iter()
-            .map(|&child_id| (child_id, self.get_node(child_id)))
-        {
-            if let Some(m) = child.mv {
-                if m == mv {
-                    next_id = Some(id);
-                }
-            }
-        }
+            .find(|&&(m, _)| m == mv)
+            .map(|&(_, child_id)| child_id);
 
         match next_id {
             // Update the current root node to the found child node.
@@ -192,176 +522,393 @@ where
     }
 
     /// From the given node, creates a child node that represents the given move
-    /// & return the ID of the new node.
+    /// & return the ID of the new node. If the transposition table already has
+    /// a node for the resulting position, that node is linked as the child
+    /// instead.
     fn make_move(&mut self, node_id: usize, mv: M) -> usize {
         let state: S;
+        let player: P;
+        let prior: f64;
 
         // Prevent double mut borrow using nested scope.
         {
-            let node = self.get_node_mut(node_id);
+            let node = &mut self.tree[node_id];
+            // The outcomes of chance events use their probability as the prior.
+            let probability = node
+                .outcomes
+                .iter()
+                .flatten()
+                .find(|&&(m, _)| m == mv)
+                .map(|&(_, probability)| probability);
+            prior = match (probability, &self.prior_fn) {
+                (Some(probability), _) => probability,
+                (None, Some(prior_fn)) => prior_fn(&node.state, mv),
+                (None, None) => 1.0 / (node.child_nodes.len() + node.untried_mvs.len()) as f64,
+            };
             node.untried_mvs.retain(|&m| m != mv);
+            player = node.state.get_current_player();
             state = node.state.from_move(mv).unwrap();
         }
 
-        let child_id = self.push_node(Node::new(Some(mv), Some(node_id), state));
-        self.get_node_mut(node_id).child_nodes.push(child_id);
+        let shared_id = match (&self.transpositions, state.position_hash()) {
+            (Some(table), Some(hash)) => table.get(&hash).copied(),
+            _ => None,
+        };
+
+        let child_id = match shared_id {
+            Some(shared_id) => {
+                self.get_node_mut(shared_id).parent_nodes.push(node_id);
+                shared_id
+            }
+            None => self.push_node(Node::new(Some(mv), player, Some(node_id), state, prior)),
+        };
+        self.get_node_mut(node_id).child_nodes.push((mv, child_id));
         child_id
     }
 
-    /// Pushes the given node onto the tree & returns the ID of it.
+    /// Pushes the given node onto the tree & returns the ID of it. The node is
+    /// added to the transposition table if it is enabled.
     fn push_node(&mut self, node: Node<P, M, ME, S>) -> usize {
         let id = self.tree.len();
+        if let (Some(table), Some(hash)) = (&mut self.transpositions, node.state.position_hash()) {
+            table.insert(hash, id);
+        }
         self.tree.push(node);
         id
     }
 
-    /// Prunes out all nodes that aren't decentants of the current root node.
-    ///
-    /// # Notes
+    /// Rebuilds the transposition table from the nodes in the tree, if it is
+    /// enabled.
+    fn rebuild_transpositions(&mut self) {
+        if let Some(table) = &mut self.transpositions {
+            table.clear();
+            for (id, node) in self.tree.iter().enumerate() {
+                if let Some(hash) = node.state.position_hash() {
+                    table.entry(hash).or_insert(id);
+                }
+            }
+        }
+    }
+
+    /// Marks the given node as proven if its children are enough to decide the
+    /// result of the game from it, and returns whether it is proven. This is
+    /// the case when the player to move has a child that is a proven win for
+    /// them, or when every move has been expanded & proven, in which case the
+    /// player to move picks the result that is best for them.
     ///
-    /// This method will make a complete copy of the node tree with only the
-    /// required nodes in, meaning that it shouldn't be done in time-critical
-    /// sections.
-    fn prune_nodes(&mut self) {
-        let mut cur_node = self.get_cur_node().clone();
-        cur_node.parent_node = None;
-        let mut n_tree = vec![cur_node];
+    /// Once a node is proven, each of its parents is checked in turn, since
+    /// nodes shared through the transposition table can be proven through a
+    /// parent that isn't on the current path.
+    fn update_proven(&mut self, node_id: usize) -> bool {
+        let draw_value = self.draw_value;
+        let node = self.get_node(node_id);
+        if node.is_proven() {
+            return true;
+        }
 
-        // Recursively append children to new tree.
-        self.append_children_to(0, &mut n_tree);
+        if let Some(outcomes) = &node.outcomes {
+            let proven = self.chance_proven(node, outcomes);
+            return self.set_proven(node_id, proven);
+        }
 
-        // Once done, replace old tree & update current node.
-        self.tree = n_tree;
-        self.cur_node_id = 0;
+        let cur_ply = node.state.get_current_player();
+        let mut children = node
+            .child_nodes
+            .iter()
+            .map(|&(_, child_id)| self.get_node(child_id));
+        let proven = match children
+            .clone()
+            .find(|child| child.is_proven_win_for(cur_ply))
+        {
+            Some(child) => child.proven.clone(),
+            None if node.is_fully_expanded() && children.all(|child| child.is_proven()) => node
+                .child_nodes
+                .iter()
+                .filter_map(|&(_, child_id)| self.get_node(child_id).proven.as_ref())
+                .max_by(|x, y| {
+                    x.reward_for(cur_ply, draw_value)
+                        .total_cmp(&y.reward_for(cur_ply, draw_value))
+                })
+                .cloned(),
+            None => None,
+        };
+
+        self.set_proven(node_id, proven)
+    }
+
+    /// Sets the proven result of the given node & returns whether it is
+    /// proven. If it is, each parent is checked to see if it can be proven
+    /// too.
+    fn set_proven(&mut self, node_id: usize, proven: Option<RolloutResult<P>>) -> bool {
+        if proven.is_none() {
+            return false;
+        }
+
+        let node = self.get_node_mut(node_id);
+        node.proven = proven;
+        for parent_id in node.parent_nodes.clone() {
+            self.update_proven(parent_id);
+        }
+        true
     }
 
-    /// Appends all of a node's children from the old tree onto the new tree.
-    /// This method will work recursively with all children & sub-children.
-    fn append_children_to(&self, c_id: usize, n_tree: &mut Vec<Node<P, M, ME, S>>) {
-        let children = n_tree[c_id].child_nodes.clone();
-        let mut n_children = vec![];
+    /// Returns the proven result of the given chance node, which is known once
+    /// every outcome has been expanded & proven. If every outcome has the same
+    /// result, that is the result of the node. Otherwise, each player gets
+    /// their expected reward over the outcomes.
+    fn chance_proven(
+        &self,
+        node: &Node<P, M, ME, S>,
+        outcomes: &[(M, f64)],
+    ) -> Option<RolloutResult<P>> {
+        if !node.is_fully_expanded() {
+            return None;
+        }
+
+        let results = node
+            .child_nodes
+            .iter()
+            .map(|&(mv, child_id)| {
+                let probability = outcomes
+                    .iter()
+                    .find(|&&(m, _)| m == mv)
+                    .map_or(0.0, |&(_, probability)| probability);
+                self.get_node(child_id)
+                    .proven
+                    .as_ref()
+                    .map(|result| (result, probability))
+            })
+            .collect::<Option<Vec<_>>>()?;
 
-        // Copy all children from the current node over to the new tree.
-        for &child_id in children.iter() {
-            n_children.push(n_tree.len());
-            let mut child = self.get_node(child_id).clone();
-            child.parent_node = Some(c_id);
-            n_tree.push(child);
+        let (first, _) = results[0];
+        if results.iter().all(|&(result, _)| result == first) {
+            return Some(first.clone());
         }
+        Some(RolloutResult::Rewards(
+            node.state
+                .get_players()
+                .into_iter()
+                .map(|ply| {
+                    let reward = results
+                        .iter()
+                        .map(|&(result, probability)| {
+                            probability * result.reward_for(ply, self.draw_value)
+                        })
+                        .sum();
+                    (ply, reward)
+                })
+                .collect(),
+        ))
+    }
 
-        // For each child, append their children to the new tree.
-        // We do this after just to keep all the children of a node together in
-        // a single block.
-        for &child_id in n_children.iter() {
-            self.append_children_to(child_id, n_tree);
+    /// Prunes out all nodes that aren't decentants of the current root node.
+    /// Nodes with several parents are kept once, and only keep the parents
+    /// that are still in the tree.
+    ///
+    /// # Notes
+    ///
+    /// This method will rebuild the whole node tree with only the required
+    /// nodes in, meaning that it shouldn't be done in time-critical sections.
+    fn prune_nodes(&mut self) {
+        // Find every node reachable from the current node, giving each one its
+        // new ID in the order they are found. This keeps all the children of a
+        // node together in a single block.
+        let mut new_ids: Vec<Option<usize>> = vec![None; self.tree.len()];
+        let mut order = vec![self.cur_node_id];
+        new_ids[self.cur_node_id] = Some(0);
+        let mut i = 0;
+        while i < order.len() {
+            for &(_, child_id) in self.get_node(order[i]).child_nodes.iter() {
+                if new_ids[child_id].is_none() {
+                    new_ids[child_id] = Some(order.len());
+                    order.push(child_id);
+                }
+            }
+            i += 1;
         }
 
-        // Update the child nodes vec with the new IDs.
-        n_tree[c_id].child_nodes = n_children;
+        // Move the reachable nodes into the new tree, updating their IDs.
+        let mut old_tree: Vec<Option<Node<P, M, ME, S>>> = std::mem::take(&mut self.tree)
+            .into_iter()
+            .map(Some)
+            .collect();
+        self.tree = order
+            .iter()
+            .map(|&old_id| {
+                let mut node = old_tree[old_id].take().unwrap();
+                node.parent_nodes = node
+                    .parent_nodes
+                    .iter()
+                    .filter_map(|&parent_id| new_ids[parent_id])
+                    .collect();
+                for (_, child_id) in node.child_nodes.iter_mut() {
+                    *child_id = new_ids[*child_id].unwrap();
+                }
+                node
+            })
+            .collect();
+
+        // Once done, update current node & the transposition table.
+        self.cur_node_id = 0;
+        self.rebuild_transpositions();
     }
 
     // Phase fns.
 
     /// Selection phase of MCTS. Selects the next child to work on & returns
-    /// the node's ID.
-    fn phase_selection(&self, node_id: usize, selection_pol: &SelectionPolicy) -> usize {
-        let node = self.get_node(node_id);
+    /// the path of node IDs from the given node to it. Since nodes can have
+    /// several parents, the path is the only record of how the node was
+    /// reached.
+    ///
+    /// Proven children are skipped, since searching them again can't change
+    /// their result. If every child of a node is proven, the node itself is
+    /// proven & the path stops at it.
+    ///
+    /// At chance nodes, an outcome is sampled by its probability instead, so
+    /// that the rewards backed up through the node average out to its expected
+    /// value. Outcomes are created the first time they are sampled.
+    fn phase_selection<T>(&mut self, node_id: usize, selection_pol: &T) -> Vec<usize>
+    where
+        T: TreePolicy<P, M, ME, S> + ?Sized,
+    {
+        let mut path = vec![node_id];
+        loop {
+            let last_id = *path.last().unwrap();
+            let node = &self.tree[last_id];
 
-        if !node.is_fully_expanded() || !node.has_children() {
-            node_id
-        } else {
-            let child_id = self.select_max_child(
-                node,
-                match selection_pol {
-                    SelectionPolicy::Ucb1(expl) => {
-                        let ex = expl.unwrap_or(UCB1_DEFAULT_EXPLORE_CONST);
-                        move |child| self.selector_ucb1(node, child, ex)
-                    }
-                },
-            );
+            if node.is_proven() {
+                return path;
+            }
 
-            self.phase_selection(child_id, selection_pol)
+            if let Some(outcomes) = &node.outcomes {
+                let &(mv, _) = outcomes
+                    .choose_weighted(&mut self.rng, |&(_, probability)| probability)
+                    .unwrap();
+                let child_id = node
+                    .child_nodes
+                    .iter()
+                    .find(|&&(m, _)| m == mv)
+                    .map(|&(_, child_id)| child_id);
+                path.push(child_id.unwrap_or_else(|| self.make_move(last_id, mv)));
+                continue;
+            }
+
+            if !node.is_fully_expanded() || !node.has_children() {
+                return path;
+            }
+
+            // Children can be proven through another parent when nodes are
+            // shared, which leaves this node to be proven here.
+            if node
+                .child_nodes
+                .iter()
+                .all(|&(_, child_id)| self.tree[child_id].is_proven())
+            {
+                self.update_proven(last_id);
+                return path;
+            }
+
+            let rng = &mut self.rng;
+            let (_, child_id) = Self::select_max_child(&self.tree, node, |child| {
+                if child.is_proven() {
+                    f64::NEG_INFINITY
+                } else {
+                    selection_pol.score(node, child, rng)
+                }
+            });
+            path.push(child_id);
         }
     }
 
     /// Expansion phase of MCTS. Selects a move at random to perform from the
-    /// given node, and creates a child node representing that move. The ID of
-    /// the child is then returned.
+    /// last node in the path, and creates a child node representing that move.
+    /// The ID of the child is then pushed onto the path.
     ///
-    /// If no move can be done, then the given node ID itself is returned. In
-    /// this case, it means that the node is at the end of the game.
-    fn phase_expansion(&mut self, node_id: usize) -> usize {
-        match self
-            .get_node_mut(node_id)
-            .untried_mvs
-            .choose(&mut rand::thread_rng())
-        {
-            Some(&mv) => self.make_move(node_id, mv),
-            None => node_id,
+    /// If no move can be done, then the path is left as is. In this case, it
+    /// means that the node is at the end of the game. Proven nodes are never
+    /// expanded either, since their proven result is backed up instead.
+    fn phase_expansion(&mut self, path: &mut Vec<usize>) {
+        let node_id = *path.last().unwrap();
+        if self.tree[node_id].is_proven() {
+            return;
+        }
+        if let Some(&mv) = self.tree[node_id].untried_mvs.choose(&mut self.rng) {
+            path.push(self.make_move(node_id, mv));
         }
     }
 
-    /// Rollout phase of MCTS. Performs a completely random game to completion
-    /// & returns the winner of that game.
-    fn phase_rollout(&self, state: &S) -> Option<P> {
-        let mut working_state = state.clone();
-        while let Some(&mv) = working_state.get_moves().choose(&mut rand::thread_rng()) {
-            working_state.make_move(mv).unwrap();
+    /// Rollout phase of MCTS. Plays out the game from the given node using the
+    /// rollout policy & returns the result. Proven nodes return their proven
+    /// result instead.
+    fn phase_rollout(&mut self, node_id: usize) -> RolloutResult<P> {
+        let node = &self.tree[node_id];
+        match &node.proven {
+            Some(result) => result.clone(),
+            None => self.rollout_pol.rollout(&node.state, &mut self.rng),
         }
-
-        working_state.get_winner()
     }
 
-    /// Backprop phase of MCTS. Updates the current node and all parents with
-    /// the winner of the rollout phase.
-    fn phase_backprop(&mut self, node_id: usize, winner: Option<P>) {
-        let mut current_node = self.get_node_mut(node_id);
-        loop {
-            current_node.update(winner);
-
-            match current_node.parent_node {
-                Some(parent_node_id) => current_node = self.get_node_mut(parent_node_id),
-                None => break,
-            }
+    /// Backprop phase of MCTS. Updates every node on the path taken during the
+    /// round with the result of the rollout phase. Proven results are
+    /// propagated up the path for as long as each parent can be proven from
+    /// its children.
+    fn phase_backprop(&mut self, path: &[usize], result: &RolloutResult<P>) {
+        let draw_value = self.draw_value;
+        let mut child_proven = false;
+        for &node_id in path.iter().rev() {
+            self.get_node_mut(node_id).update(result, draw_value);
+            child_proven = if child_proven {
+                self.update_proven(node_id)
+            } else {
+                self.get_node(node_id).is_proven()
+            };
         }
     }
 
-    /// Action selection phase of MCTS. Selects the move with the best chance of
-    /// winning from the current root node.
+    /// Action selection phase of MCTS. Selects the move to play from the
+    /// current root node. A child that is a proven win is always played, and
+    /// children that are proven losses are only played if there is no other
+    /// choice. Otherwise, the final move strategy is used.
     fn phase_action_select(&self) -> M {
-        let child_id = self.select_max_child(self.get_cur_node(), |child| {
-            (child.wins as f64) / (child.visits as f64)
+        let final_move = self.final_move;
+        let root = self.get_cur_node();
+        let cur_ply = root.state.get_current_player();
+        let (most_visits, best_win_rate) = root
+            .child_nodes
+            .iter()
+            .map(|&(_, child_id)| self.get_node(child_id))
+            .fold((0, 0.0), |(visits, win_rate): (u64, f64), child| {
+                (visits.max(child.visits), win_rate.max(child.win_rate()))
+            });
+        let (mv, _) = Self::select_max_child(&self.tree, root, |child| {
+            if child.is_proven_win_for(cur_ply) {
+                f64::INFINITY
+            } else if child.is_proven_loss_for(cur_ply) {
+                f64::NEG_INFINITY
+            } else {
+                final_move.score(child, most_visits, best_win_rate)
+            }
         });
-        self.get_node(child_id).mv.unwrap()
+        mv
     }
 
     // Phase helper fns.
 
-    /// Returns the ID of the child node that scored highest on some given
-    /// predicate.
+    /// Returns the move & ID of the child node that scored highest on some
+    /// given predicate. Each child is scored exactly once, and the last child
+    /// wins any ties. Scores are compared with [`f64::total_cmp`], so NaN
+    /// scores rank above every other score instead of panicking.
     fn select_max_child<'a, F: FnMut(&'a Node<P, M, ME, S>) -> f64>(
-        &'a self,
+        tree: &'a [Node<P, M, ME, S>],
         node: &'a Node<P, M, ME, S>,
         mut selector: F,
-    ) -> usize {
-        let mut children = node
-            .child_nodes
+    ) -> (M, usize) {
+        node.child_nodes
             .iter()
-            .map(|&child_id| (child_id, self.get_node(child_id)))
-            .collect::<Vec<(usize, &Node<P, M, ME, S>)>>();
-        children.sort_by(|&(_, x), &(_, y)| selector(x).partial_cmp(&selector(y)).unwrap());
-        children.last().unwrap().0
-    }
-
-    /// The standard UCB1 selector function.
-    fn selector_ucb1(
-        &self,
-        node: &Node<P, M, ME, S>,
-        child: &Node<P, M, ME, S>,
-        explore_const: f64,
-    ) -> f64 {
-        (child.wins as f64) / (child.visits as f64)
-            + explore_const * ((node.visits as f64).ln() / (child.visits as f64)).sqrt()
+            .map(|&edge| (edge, selector(&tree[edge.1])))
+            .max_by(|(_, x), (_, y)| x.total_cmp(y))
+            .unwrap()
+            .0
     }
 
     // Util fns.
@@ -381,3 +928,536 @@ where
         &mut self.tree[node_id]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connect4;
+    use crate::mnk::{self, Player};
+
+    /// The tic-tac-toe position where X can win straight away on c1.
+    const X_WINS: [&str; 4] = ["a1", "a2", "b1", "b2"];
+
+    /// The tic-tac-toe position where X's last move on b3 draws the game.
+    const X_DRAWS: [&str; 8] = ["a1", "b1", "c1", "b2", "a2", "a3", "c2", "c3"];
+
+    /// The tic-tac-toe position where X has to block O on b3, & every other
+    /// move loses.
+    const X_BLOCKS: [&str; 4] = ["a1", "b2", "c1", "b1"];
+
+    /// Returns the tic-tac-toe game with the given moves played.
+    fn tic_tac_toe(moves: &[&str]) -> mnk::Game {
+        let mut game = mnk::Game::tic_tac_toe();
+        for mv in moves {
+            game.make_move(mv.parse().unwrap()).unwrap();
+        }
+        game
+    }
+
+    /// A three-player game where each player picks 0 or 1 once, & is rewarded
+    /// with the number they picked.
+    #[derive(Clone, Debug)]
+    struct PickGame {
+        picks: Vec<u8>,
+    }
+
+    impl fmt::Display for PickGame {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{:?}", self.picks)
+        }
+    }
+
+    impl GameState<u8, u8, ()> for PickGame {
+        fn make_move(&mut self, mv: u8) -> Result<(), ()> {
+            if self.picks.len() == 3 || mv > 1 {
+                return Err(());
+            }
+            self.picks.push(mv);
+            Ok(())
+        }
+
+        fn get_moves(&self) -> Vec<u8> {
+            if self.picks.len() == 3 {
+                vec![]
+            } else {
+                vec![0, 1]
+            }
+        }
+
+        fn get_winner(&self) -> Option<u8> {
+            None
+        }
+
+        fn get_current_player(&self) -> u8 {
+            self.picks.len() as u8 % 3
+        }
+
+        fn get_prev_player(&self) -> u8 {
+            (self.get_current_player() + 2) % 3
+        }
+
+        fn get_players(&self) -> Vec<u8> {
+            vec![0, 1, 2]
+        }
+
+        fn get_rewards(&self) -> Option<Vec<(u8, f64)>> {
+            if self.picks.len() == 3 {
+                Some(
+                    self.picks
+                        .iter()
+                        .enumerate()
+                        .map(|(ply, &pick)| (ply as u8, pick as f64))
+                        .collect(),
+                )
+            } else {
+                None
+            }
+        }
+    }
+
+    /// A two-player game where player 0 picks 0 or 1 twice in a row, & then
+    /// player 1 picks once. Player 0 is rewarded with the mean of their picks
+    /// & player 1 with their pick.
+    #[derive(Clone, Debug)]
+    struct DoublePickGame {
+        picks: Vec<u8>,
+    }
+
+    impl fmt::Display for DoublePickGame {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{:?}", self.picks)
+        }
+    }
+
+    impl GameState<u8, u8, ()> for DoublePickGame {
+        fn make_move(&mut self, mv: u8) -> Result<(), ()> {
+            if self.picks.len() == 3 || mv > 1 {
+                return Err(());
+            }
+            self.picks.push(mv);
+            Ok(())
+        }
+
+        fn get_moves(&self) -> Vec<u8> {
+            if self.picks.len() == 3 {
+                vec![]
+            } else {
+                vec![0, 1]
+            }
+        }
+
+        fn get_winner(&self) -> Option<u8> {
+            None
+        }
+
+        fn get_current_player(&self) -> u8 {
+            match self.picks.len() {
+                2 => 1,
+                _ => 0,
+            }
+        }
+
+        fn get_prev_player(&self) -> u8 {
+            match self.picks.len() {
+                1 | 2 => 0,
+                _ => 1,
+            }
+        }
+
+        fn get_rewards(&self) -> Option<Vec<(u8, f64)>> {
+            if self.picks.len() == 3 {
+                Some(vec![
+                    (0, f64::from(self.picks[0] + self.picks[1]) / 2.0),
+                    (1, f64::from(self.picks[2])),
+                ])
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Returns the root child of the given AI that the given move leads to.
+    fn root_child<'a, S>(
+        ai: &'a Mcts<Player, mnk::Move, mnk::MoveError, S>,
+        mv: &str,
+    ) -> &'a Node<Player, mnk::Move, mnk::MoveError, S>
+    where
+        S: GameState<Player, mnk::Move, mnk::MoveError>,
+    {
+        let mv = mv.parse().unwrap();
+        let &(_, child_id) = ai
+            .get_cur_node()
+            .child_nodes
+            .iter()
+            .find(|&&(m, _)| m == mv)
+            .unwrap();
+        ai.get_node(child_id)
+    }
+
+    #[test]
+    fn seeded_searches_are_reproducible() {
+        let game = connect4::Game::new();
+        let search = || {
+            let mut ai = Mcts::with_seed(connect4::Player::Red, &game, 42);
+            ai.select_next_move(&SearchLimit::Rounds(500), &SelectionPolicy::Ucb1(None))
+        };
+        let (first, second) = (search(), search());
+        assert_eq!(first.rounds, 500);
+        assert_eq!(first.best_move, second.best_move);
+        assert_eq!(first.children, second.children);
+    }
+
+    #[test]
+    #[should_panic(expected = "Depth-limited rollouts only value two-player games")]
+    fn depth_limited_rejects_games_with_more_than_two_players() {
+        let game = PickGame { picks: vec![] };
+        let policy = DepthLimited::new(1, |_: &PickGame| 0.5);
+        policy.rollout(&game, &mut StdRng::seed_from_u64(0));
+    }
+
+    #[test]
+    fn wins_track_the_player_that_moved_into_each_node() {
+        let game = PickGame { picks: vec![] };
+        let mut ai = Mcts::with_seed(0, &game, 0);
+        ai.search(&SearchLimit::Rounds(200), &SelectionPolicy::Ucb1(None));
+
+        assert_eq!(ai.get_cur_node().player(), 2);
+        for node in ai.tree.iter() {
+            assert_eq!(node.wins(), node.reward_of(node.player()));
+        }
+
+        // Each player only gets a reward from their own pick, so every node
+        // reached by picking 1 is a win for the player that picked it, no
+        // matter what the other players do.
+        for node in ai.tree.iter().filter(|node| node.mv().is_some()) {
+            let expected = node.mv().unwrap() as f64;
+            assert_eq!(node.wins(), expected * node.visits as f64);
+        }
+
+        // Each player in turn moves into the next level of the tree.
+        let mut depth = 0;
+        let mut node_id = ai.cur_node_id;
+        while let Some(&(_, child_id)) = ai.get_node(node_id).child_nodes.first() {
+            assert_eq!(ai.get_node(child_id).player(), depth);
+            depth += 1;
+            node_id = child_id;
+        }
+        assert_eq!(depth, 3);
+        assert_eq!(ai.phase_action_select(), 1);
+    }
+
+    #[test]
+    fn players_can_move_twice_in_a_row() {
+        let mut game = DoublePickGame { picks: vec![] };
+        let mut ai = Mcts::with_seed(0, &game, 0);
+        ai.search(&SearchLimit::Rounds(200), &SelectionPolicy::Ucb1(None));
+
+        // The root belongs to the player before the start, & each node below
+        // it to the player that moved into it, even when that player moves
+        // again straight after.
+        assert_eq!(ai.get_cur_node().player(), 1);
+        for node in ai.tree.iter() {
+            assert_eq!(node.wins(), node.reward_of(node.player()));
+            for &(_, child_id) in node.child_nodes.iter() {
+                assert_eq!(
+                    ai.get_node(child_id).player(),
+                    node.state.get_current_player()
+                );
+            }
+        }
+
+        // Player 0 picks 1 with both of their moves.
+        for _ in 0..2 {
+            let report =
+                ai.select_next_move(&SearchLimit::Rounds(200), &SelectionPolicy::Ucb1(None));
+            assert_eq!(report.best_move, 1);
+            game.make_move(report.best_move).unwrap();
+            ai.update_target_move(report.best_move);
+        }
+        assert_eq!(ai.get_cur_node().player(), 0);
+        assert_eq!(game.get_current_player(), 1);
+        ai.update_opponent_move(0);
+        assert_eq!(ai.get_cur_node().player(), 1);
+    }
+
+    #[test]
+    fn draws_are_stored_with_the_draw_value() {
+        let game = tic_tac_toe(&X_DRAWS);
+        for &draw_value in [0.0, 0.25, DEFAULT_DRAW_VALUE, 1.0].iter() {
+            let mut ai = Mcts::with_seed(Player::X, &game, 0).with_draw_value(draw_value);
+            ai.search(&SearchLimit::Rounds(10), &SelectionPolicy::Ucb1(None));
+
+            // Both players get the draw value, whichever one moved.
+            let child = root_child(&ai, "b3");
+            let visits = child.visits as f64;
+            assert!(visits > 0.0);
+            assert_eq!(child.wins(), draw_value * visits);
+            assert_eq!(child.wins_sq(), draw_value * draw_value * visits);
+            let root = ai.get_cur_node();
+            assert_eq!(root.wins(), draw_value * root.visits as f64);
+            for &ply in [Player::X, Player::O].iter() {
+                assert_eq!(child.reward_of(ply), draw_value * visits);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Draw value must be in [0, 1]")]
+    fn draw_value_below_0_is_rejected() {
+        Mcts::with_seed(Player::X, &mnk::Game::tic_tac_toe(), 0).with_draw_value(-1.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Draw value must be in [0, 1]")]
+    fn nan_draw_value_is_rejected() {
+        Mcts::with_seed(Player::X, &mnk::Game::tic_tac_toe(), 0).with_draw_value(f64::NAN);
+    }
+
+    #[test]
+    #[should_panic(expected = "Selecting move but the game has ended")]
+    fn selecting_a_move_after_the_game_has_ended_panics() {
+        let mut moves = X_DRAWS.to_vec();
+        moves.push("b3");
+        let game = tic_tac_toe(&moves);
+        let mut ai = Mcts::with_seed(Player::O, &game, 0);
+        ai.select_next_move(&SearchLimit::Rounds(10), &SelectionPolicy::Ucb1(None));
+    }
+
+    #[test]
+    fn nan_scores_dont_panic() {
+        let game = mnk::Game::tic_tac_toe();
+        for &policy in [
+            SelectionPolicy::Ucb1(Some(f64::NAN)),
+            SelectionPolicy::Puct(Some(f64::NAN)),
+        ]
+        .iter()
+        {
+            let mut ai = Mcts::with_seed(Player::X, &game, 0);
+            let report = ai.select_next_move(&SearchLimit::Rounds(200), &policy);
+            assert!(game.get_moves().contains(&report.best_move));
+        }
+    }
+
+    #[test]
+    fn solver_proves_a_forced_win_and_stops_early() {
+        let game = tic_tac_toe(&X_WINS);
+        let mut ai = Mcts::with_seed(Player::X, &game, 0);
+        let report =
+            ai.select_next_move(&SearchLimit::Rounds(10_000), &SelectionPolicy::Ucb1(None));
+
+        assert!(report.rounds < 10_000);
+        assert_eq!(report.best_move, "c1".parse().unwrap());
+        assert_eq!(
+            ai.get_cur_node().proven(),
+            Some(&RolloutResult::Winner(Some(Player::X)))
+        );
+    }
+
+    #[test]
+    fn solver_plays_a_proven_win_immediately() {
+        let game = tic_tac_toe(&X_WINS);
+        let mut ai = Mcts::with_seed(Player::X, &game, 0)
+            .with_final_move_strategy(FinalMoveStrategy::Robust);
+        ai.search(&SearchLimit::Rounds(10_000), &SelectionPolicy::Ucb1(None));
+
+        // Make every other move look far better, which the proven win should
+        // still beat.
+        let win_id = ai
+            .get_cur_node()
+            .child_nodes
+            .iter()
+            .find(|&&(m, _)| m == "c1".parse().unwrap())
+            .unwrap()
+            .1;
+        for (_, child_id) in ai.get_cur_node().child_nodes.clone() {
+            if child_id != win_id {
+                let child = ai.get_node_mut(child_id);
+                child.visits = 1_000_000;
+                child.wins = 1_000_000.0;
+            }
+        }
+        assert_eq!(ai.phase_action_select(), "c1".parse().unwrap());
+    }
+
+    #[test]
+    fn solver_never_picks_a_proven_loss() {
+        let game = tic_tac_toe(&X_BLOCKS);
+        let mut ai = Mcts::with_seed(Player::X, &game, 0);
+        ai.search(&SearchLimit::Rounds(300), &SelectionPolicy::Ucb1(None));
+
+        // Every move but the block lets O win on b3.
+        let mut losses = 0;
+        for (mv, child_id) in ai.get_cur_node().child_nodes.clone() {
+            if ai.get_node(child_id).is_proven_loss_for(Player::X) {
+                assert_ne!(mv, "b3".parse().unwrap());
+                losses += 1;
+                let child = ai.get_node_mut(child_id);
+                child.visits = 1_000_000;
+                child.wins = 1_000_000.0;
+            }
+        }
+        assert_eq!(losses, 4);
+        assert_eq!(ai.phase_action_select(), "b3".parse().unwrap());
+    }
+
+    #[test]
+    fn solver_skips_proven_children() {
+        let game = tic_tac_toe(&X_BLOCKS);
+        let mut ai = Mcts::with_seed(Player::X, &game, 0);
+        ai.search(&SearchLimit::Rounds(100), &SelectionPolicy::Ucb1(None));
+
+        let proven_visits: Vec<(mnk::Move, u64)> = ai
+            .get_cur_node()
+            .child_nodes
+            .iter()
+            .filter(|&&(_, child_id)| ai.get_node(child_id).is_proven())
+            .map(|&(mv, child_id)| (mv, ai.get_node(child_id).visits))
+            .collect();
+        assert!(!proven_visits.is_empty());
+
+        ai.search(&SearchLimit::Rounds(100), &SelectionPolicy::Ucb1(None));
+        for (mv, visits) in proven_visits {
+            assert_eq!(root_child(&ai, &mv.to_string()).visits, visits);
+        }
+    }
+
+    #[test]
+    fn solver_doesnt_expand_proven_nodes() {
+        let game = tic_tac_toe(&X_WINS);
+        let mut ai = Mcts::with_seed(Player::X, &game, 0);
+        ai.search(&SearchLimit::Rounds(10_000), &SelectionPolicy::Ucb1(None));
+        assert!(ai.get_cur_node().is_proven());
+        assert!(!ai.get_cur_node().is_fully_expanded());
+
+        // A search always performs at least one round, which should back up
+        // the proven result of the root without adding to the tree.
+        let (tree_size, visits, wins) = (
+            ai.tree.len(),
+            ai.get_cur_node().visits,
+            ai.get_cur_node().wins,
+        );
+        assert_eq!(
+            ai.search(&SearchLimit::Rounds(5), &SelectionPolicy::Ucb1(None)),
+            1
+        );
+        assert_eq!(ai.tree.len(), tree_size);
+        assert_eq!(ai.get_cur_node().visits, visits + 1);
+        // The root belongs to O, who moved last, so O gains nothing.
+        assert_eq!(ai.get_cur_node().wins, wins);
+    }
+
+    #[test]
+    fn solver_proves_every_parent_of_a_shared_node() {
+        let game = tic_tac_toe(&[]);
+        let mut ai = Mcts::with_seed(Player::X, &game, 0).with_transpositions();
+
+        // Both orders end with X threatening b1, a2 & b2 at once, which O
+        // can't stop.
+        let follow = |ai: &mut Mcts<_, _, _, _>, moves: &[&str]| {
+            let mut path = vec![ai.cur_node_id];
+            for mv in moves {
+                let node_id = *path.last().unwrap();
+                let mv = mv.parse().unwrap();
+                let child_id = ai
+                    .get_node(node_id)
+                    .child_nodes
+                    .iter()
+                    .find(|&&(m, _)| m == mv)
+                    .map(|&(_, child_id)| child_id);
+                path.push(child_id.unwrap_or_else(|| ai.make_move(node_id, mv)));
+            }
+            path
+        };
+        let first = follow(&mut ai, &["a1", "b3", "c1", "c2", "a3"]);
+        let second = follow(&mut ai, &["a3", "b3", "c1", "c2", "a1"]);
+        let shared_id = first[5];
+        assert_eq!(second[5], shared_id);
+
+        // Prove the shared node through the first order only, by answering
+        // each reply of O with a win for X.
+        for &(reply, win) in [("b1", "a2"), ("a2", "b1"), ("b2", "b1"), ("c3", "b1")].iter() {
+            let mut moves = vec!["a1", "b3", "c1", "c2", "a3"];
+            moves.extend_from_slice(&[reply, win]);
+            let path = follow(&mut ai, &moves);
+            ai.phase_backprop(&path, &RolloutResult::Winner(Some(Player::X)));
+        }
+        let won = Some(&RolloutResult::Winner(Some(Player::X)));
+        assert_eq!(ai.get_node(shared_id).proven(), won);
+
+        // The parent from the second order was never on a backprop path, but
+        // X can move into the shared node from it, so it is won for X too.
+        let other_parent = ai.get_node(second[4]);
+        assert!(!other_parent.is_fully_expanded());
+        assert_eq!(other_parent.proven(), won);
+        assert_eq!(ai.get_node(first[4]).proven(), won);
+    }
+
+    #[test]
+    fn transpositions_share_one_node_between_move_orders() {
+        let game = connect4::Game::new();
+        let mut ai = Mcts::with_seed(connect4::Player::Red, &game, 0).with_transpositions();
+
+        // Both orders reach the same position, with the same player to move.
+        let mut paths = vec![];
+        for order in [[0, 1, 2], [2, 1, 0]].iter() {
+            let mut path = vec![ai.cur_node_id];
+            for &mv in order.iter() {
+                let node_id = *path.last().unwrap();
+                path.push(ai.make_move(node_id, mv));
+            }
+            ai.phase_backprop(&path, &RolloutResult::Winner(Some(connect4::Player::Red)));
+            paths.push(path);
+        }
+
+        let shared_id = paths[0][3];
+        assert_eq!(paths[1][3], shared_id);
+        assert_eq!(ai.tree.len(), 6);
+        let mut parents = ai.get_node(shared_id).parent_nodes.clone();
+        parents.sort_unstable();
+        assert_eq!(parents, [paths[0][2], paths[1][2]]);
+
+        // The shared node was visited through both parents, while each other
+        // node was only on one path.
+        assert_eq!(ai.get_cur_node().visits, 2);
+        assert_eq!(ai.get_node(shared_id).visits, 2);
+        assert_eq!(ai.get_node(shared_id).wins, 2.0);
+        for path in paths.iter() {
+            for &node_id in path[1..3].iter() {
+                assert_eq!(ai.get_node(node_id).visits, 1);
+            }
+        }
+
+        // Playing the first move of one order drops the other parent of the
+        // shared node when pruning.
+        ai.update_target_move(0);
+        ai.prune_nodes();
+        assert_eq!(ai.tree.len(), 3);
+        for node in ai.tree.iter() {
+            assert!(node.parent_nodes.iter().all(|&id| id < ai.tree.len()));
+            assert!(node.child_nodes.iter().all(|&(_, id)| id < ai.tree.len()));
+        }
+        let (_, after_yellow) = ai.get_cur_node().child_nodes[0];
+        let (_, shared_id) = ai.get_node(after_yellow).child_nodes[0];
+        assert_eq!(ai.get_node(shared_id).parent_nodes, [after_yellow]);
+        assert_eq!(ai.get_node(shared_id).visits, 2);
+
+        // The table is rebuilt to only hold the nodes that are left.
+        let table = ai.transpositions.as_ref().unwrap();
+        assert_eq!(table.len(), 3);
+        for (&hash, &id) in table.iter() {
+            assert_eq!(ai.get_node(id).state.position_hash(), Some(hash));
+        }
+    }
+
+    #[test]
+    fn max_robust_stops_at_the_search_limit() {
+        let game = connect4::Game::new();
+        for &rounds in [1, 10, 50].iter() {
+            let mut ai = Mcts::with_seed(connect4::Player::Red, &game, 0)
+                .with_final_move_strategy(FinalMoveStrategy::MaxRobust);
+            let report =
+                ai.select_next_move(&SearchLimit::Rounds(rounds), &SelectionPolicy::Ucb1(None));
+            assert_eq!(report.rounds, rounds);
+        }
+    }
+}
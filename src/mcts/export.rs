@@ -0,0 +1,420 @@
+use super::{Mcts, Node, SelectionPolicy, TreePolicy};
+use crate::game::GameState;
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::io::{self, Write};
+
+/// The limits & settings used when exporting the search tree.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExportOptions {
+    /// The deepest level of the tree to export, where the root is at depth 0.
+    max_depth: Option<usize>,
+    /// The number of visits a node needs to be exported. The root is always
+    /// exported.
+    min_visits: u64,
+    /// The selection policy used to score each edge.
+    policy: SelectionPolicy,
+}
+
+impl ExportOptions {
+    /// Constructs the default options, which export the whole tree & score
+    /// each edge with UCB1 using its default exploration constant.
+    pub fn new() -> Self {
+        ExportOptions {
+            max_depth: None,
+            min_visits: 0,
+            policy: SelectionPolicy::Ucb1(None),
+        }
+    }
+
+    /// Only exports nodes up to the given depth below the root.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Only exports nodes that have been visited at least the given number of
+    /// times, along with their descendants that pass the same check.
+    pub fn with_min_visits(mut self, min_visits: u64) -> Self {
+        self.min_visits = min_visits;
+        self
+    }
+
+    /// Sets the selection policy used to score each edge, which should be the
+    /// policy that the search used. Thompson sampling scores are sampled with
+    /// a fixed seed, so the same tree always exports the same scores.
+    pub fn with_policy(mut self, policy: SelectionPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Returns whether a child at the given depth should be exported.
+    fn includes<P, M, ME, S>(&self, child: &Node<P, M, ME, S>, depth: usize) -> bool
+    where
+        P: Copy + PartialEq + ToString + fmt::Debug,
+        M: Copy + PartialEq + fmt::Debug,
+        ME: Copy + fmt::Debug,
+        S: GameState<P, M, ME>,
+    {
+        self.max_depth.is_none_or(|max| depth <= max) && child.visits >= self.min_visits
+    }
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P, M, ME, S, R> Mcts<P, M, ME, S, R>
+where
+    P: Copy + PartialEq + ToString + fmt::Debug,
+    M: Copy + PartialEq + fmt::Debug,
+    ME: Copy + fmt::Debug,
+    S: GameState<P, M, ME>,
+    R: Rng,
+{
+    /// Writes the subtree under the current root node as a Graphviz DOT graph.
+    /// Each node is labelled with the move into it, the player that made it,
+    /// its visits & wins, and each edge with its move & score under the
+    /// policy of the options. Nodes that are shared between several parents
+    /// are written once, with an edge from each exported parent.
+    pub fn export_dot<W: Write>(&self, mut writer: W, options: &ExportOptions) -> io::Result<()> {
+        writeln!(writer, "digraph mcts {{")?;
+        writeln!(writer, "    node [shape=box];")?;
+
+        let mut rng = StdRng::seed_from_u64(0);
+        self.walk_export(options, |node_id, edges| {
+            writeln!(
+                writer,
+                "    n{} [label=\"{}\"];",
+                node_id,
+                escape(&self.node_label(node_id))
+            )?;
+            for &(mv, child_id) in edges {
+                writeln!(
+                    writer,
+                    "    n{} -> n{} [label=\"{}\"];",
+                    node_id,
+                    child_id,
+                    escape(&format!(
+                        "{:?}\nscore: {:.3}",
+                        mv,
+                        self.edge_score(options, node_id, child_id, &mut rng)
+                    ))
+                )?;
+            }
+            Ok(())
+        })?;
+
+        writeln!(writer, "}}")
+    }
+
+    /// Writes the subtree under the current root node as JSON. The output is
+    /// an object with the ID of the root & a flat array of the exported nodes.
+    /// Each node is an object with its ID, player, visits, wins, win rate &
+    /// proven result, along with an array of its exported child edges, which
+    /// hold the move, the ID of the child & the score under the policy of the
+    /// options. Nodes that are shared between several parents are written
+    /// once, with an edge from each exported parent.
+    pub fn export_json<W: Write>(&self, mut writer: W, options: &ExportOptions) -> io::Result<()> {
+        write!(writer, "{{\"root\":{},\"nodes\":[", self.cur_node_id)?;
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut first = true;
+        self.walk_export(options, |node_id, edges| {
+            if !first {
+                write!(writer, ",")?;
+            }
+            first = false;
+
+            let node = self.get_node(node_id);
+            let proven = node
+                .proven
+                .as_ref()
+                .map(|result| format!("\"{}\"", escape(&format!("{:?}", result))));
+            write!(
+                writer,
+                "{{\"id\":{},\"player\":\"{}\",\"visits\":{},\"wins\":{},\
+                 \"win_rate\":{},\"proven\":{},\"children\":[",
+                node_id,
+                escape(&node.player.to_string()),
+                node.visits,
+                json_number(node.wins),
+                json_number(node.win_rate()),
+                proven.unwrap_or_else(|| "null".to_owned()),
+            )?;
+            for (i, &(mv, child_id)) in edges.iter().enumerate() {
+                if i > 0 {
+                    write!(writer, ",")?;
+                }
+                write!(
+                    writer,
+                    "{{\"move\":\"{}\",\"child\":{},\"score\":{}}}",
+                    escape(&format!("{:?}", mv)),
+                    child_id,
+                    json_number(self.edge_score(options, node_id, child_id, &mut rng))
+                )?;
+            }
+            write!(writer, "]}}")
+        })?;
+
+        writeln!(writer, "]}}")
+    }
+
+    // Export helper fns.
+
+    /// Walks the exported nodes breadth first, so each node is given its
+    /// shallowest depth, & calls the given fn once for each node with its
+    /// exported child edges.
+    fn walk_export<F>(&self, options: &ExportOptions, mut visit: F) -> io::Result<()>
+    where
+        F: FnMut(usize, &[(M, usize)]) -> io::Result<()>,
+    {
+        let mut depths = HashMap::new();
+        let mut queue = VecDeque::new();
+        depths.insert(self.cur_node_id, 0);
+        queue.push_back(self.cur_node_id);
+        while let Some(node_id) = queue.pop_front() {
+            let depth = depths[&node_id];
+            let edges: Vec<(M, usize)> = self
+                .get_node(node_id)
+                .child_nodes
+                .iter()
+                .filter(|&&(_, child_id)| options.includes(self.get_node(child_id), depth + 1))
+                .copied()
+                .collect();
+            visit(node_id, &edges)?;
+
+            for &(_, child_id) in edges.iter() {
+                if let Entry::Vacant(entry) = depths.entry(child_id) {
+                    entry.insert(depth + 1);
+                    queue.push_back(child_id);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the score of the edge from the given parent to the given child
+    /// under the policy of the options.
+    fn edge_score(
+        &self,
+        options: &ExportOptions,
+        parent_id: usize,
+        child_id: usize,
+        rng: &mut dyn RngCore,
+    ) -> f64 {
+        options
+            .policy
+            .score(self.get_node(parent_id), self.get_node(child_id), rng)
+    }
+
+    /// Returns the label of the given node in the DOT graph.
+    fn node_label(&self, node_id: usize) -> String {
+        let node = self.get_node(node_id);
+        let mut label = match (node_id == self.cur_node_id, node.mv) {
+            (true, _) | (_, None) => "root".to_owned(),
+            (false, Some(mv)) => format!("{:?}", mv),
+        };
+        label = format!(
+            "{}\nplayer: {}\nvisits: {}\nwins: {}",
+            label,
+            node.player.to_string(),
+            node.visits,
+            node.wins
+        );
+        if let Some(result) = &node.proven {
+            label = format!("{}\nproven: {:?}", label, result);
+        }
+
+        label
+    }
+}
+
+// Util fns.
+
+/// Escapes the given text so that it can be placed inside a double quoted DOT
+/// or JSON string.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            ch if ch.is_control() => out.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => out.push(ch),
+        }
+    }
+
+    out
+}
+
+/// Formats the given number for JSON, which has no infinity or NaN, so those
+/// are written as null.
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connect4::{Game, Move, MoveError, Player};
+    use crate::{SearchLimit, SelectionPolicy};
+
+    type TestMcts = Mcts<Player, Move, MoveError, Game>;
+
+    /// Returns an AI that has searched the empty board.
+    fn searched_ai() -> TestMcts {
+        let mut ai = Mcts::with_seed(Player::Red, &Game::new(), 0);
+        ai.search(&SearchLimit::Rounds(2000), &SelectionPolicy::Ucb1(None));
+        ai
+    }
+
+    /// Returns the number of nodes below & including the given node that the
+    /// options should export.
+    fn expected_nodes(
+        ai: &TestMcts,
+        node_id: usize,
+        depth: usize,
+        options: &ExportOptions,
+    ) -> usize {
+        1 + ai
+            .get_node(node_id)
+            .child_nodes
+            .iter()
+            .filter(|&&(_, child_id)| options.includes(ai.get_node(child_id), depth + 1))
+            .map(|&(_, child_id)| expected_nodes(ai, child_id, depth + 1, options))
+            .sum::<usize>()
+    }
+
+    /// Returns the numbers that follow each occurrence of the given key.
+    fn numbers_after(text: &str, key: &str) -> Vec<u64> {
+        text.match_indices(key)
+            .map(|(i, _)| {
+                let digits: String = text[i + key.len()..]
+                    .chars()
+                    .take_while(|ch| ch.is_ascii_digit())
+                    .collect();
+                digits.parse().unwrap()
+            })
+            .collect()
+    }
+
+    /// Returns the DOT graph of the given AI.
+    fn dot(ai: &TestMcts, options: &ExportOptions) -> String {
+        let mut out = vec![];
+        ai.export_dot(&mut out, options).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    /// Returns the JSON export of the given AI.
+    fn json(ai: &TestMcts, options: &ExportOptions) -> String {
+        let mut out = vec![];
+        ai.export_json(&mut out, options).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn exports_stop_at_the_max_depth() {
+        let ai = searched_ai();
+        let children = ai.get_cur_node().child_nodes.len();
+
+        let options = ExportOptions::new().with_max_depth(0);
+        assert_eq!(numbers_after(&dot(&ai, &options), "visits: ").len(), 1);
+        assert!(!dot(&ai, &options).contains("->"));
+        assert_eq!(json(&ai, &options).matches("\"id\":").count(), 1);
+
+        let options = ExportOptions::new().with_max_depth(1);
+        let dot = dot(&ai, &options);
+        assert_eq!(numbers_after(&dot, "visits: ").len(), 1 + children);
+        assert_eq!(dot.matches("->").count(), children);
+        assert_eq!(json(&ai, &options).matches("\"id\":").count(), 1 + children);
+
+        // Without limits, every node is exported.
+        let options = ExportOptions::new();
+        assert_eq!(
+            expected_nodes(&ai, ai.cur_node_id, 0, &options),
+            ai.tree.len()
+        );
+        assert_eq!(
+            json(&ai, &options).matches("\"id\":").count(),
+            ai.tree.len()
+        );
+    }
+
+    #[test]
+    fn exports_skip_nodes_with_few_visits() {
+        let ai = searched_ai();
+        let options = ExportOptions::new().with_min_visits(20);
+        let expected = expected_nodes(&ai, ai.cur_node_id, 0, &options);
+        assert!(1 < expected && expected < ai.tree.len());
+
+        let dot_visits = numbers_after(&dot(&ai, &options), "visits: ");
+        assert_eq!(dot_visits.len(), expected);
+        assert!(dot_visits.iter().all(|&visits| visits >= 20));
+
+        let json_visits = numbers_after(&json(&ai, &options), "\"visits\":");
+        assert_eq!(json_visits.len(), expected);
+        assert!(json_visits.iter().all(|&visits| visits >= 20));
+    }
+
+    #[test]
+    fn exports_combine_both_limits() {
+        let ai = searched_ai();
+        let options = ExportOptions::new().with_max_depth(2).with_min_visits(20);
+        let expected = expected_nodes(&ai, ai.cur_node_id, 0, &options);
+        let only_depth = expected_nodes(
+            &ai,
+            ai.cur_node_id,
+            0,
+            &ExportOptions::new().with_max_depth(2),
+        );
+        assert!(expected < only_depth);
+        assert_eq!(
+            numbers_after(&dot(&ai, &options), "visits: ").len(),
+            expected
+        );
+        assert_eq!(json(&ai, &options).matches("\"id\":").count(), expected);
+    }
+
+    #[test]
+    fn json_writes_shared_nodes_once() {
+        let mut ai = Mcts::with_seed(Player::Red, &Game::new(), 0).with_transpositions();
+        ai.search(&SearchLimit::Rounds(2000), &SelectionPolicy::Ucb1(None));
+        let edges: usize = ai.tree.iter().map(|node| node.child_nodes.len()).sum();
+        assert!(edges > ai.tree.len() - 1);
+
+        let json = json(&ai, &ExportOptions::new());
+        assert_eq!(json.matches("\"id\":").count(), ai.tree.len());
+        assert_eq!(json.matches("\"child\":").count(), edges);
+        assert!(json.starts_with(&format!("{{\"root\":{},", ai.cur_node_id)));
+    }
+
+    #[test]
+    fn exports_score_edges_with_the_given_policy() {
+        let ai = searched_ai();
+
+        // Without exploration, UCB1 & PUCT both score each edge by its win
+        // rate.
+        let greedy = |policy| json(&ai, &ExportOptions::new().with_policy(policy));
+        assert_eq!(
+            greedy(SelectionPolicy::Ucb1(Some(0.0))),
+            greedy(SelectionPolicy::Puct(Some(0.0)))
+        );
+        assert_ne!(
+            greedy(SelectionPolicy::Ucb1(None)),
+            greedy(SelectionPolicy::Puct(None))
+        );
+        assert!(dot(&ai, &ExportOptions::new()).contains("score: "));
+    }
+}
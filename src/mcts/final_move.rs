@@ -0,0 +1,150 @@
+use super::Node;
+use crate::game::GameState;
+use std::fmt;
+
+/// Default constant used by [`FinalMoveStrategy::Secure`].
+pub const SECURE_DEFAULT_CONST: f64 = 1.0;
+
+/// The strategy used to pick the move to play from the root's children once
+/// the search has finished. Children that were never visited have no
+/// statistics to go on, so every strategy ranks them below any visited child.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "persist", derive(serde::Serialize, serde::Deserialize))]
+pub enum FinalMoveStrategy {
+    /// Picks the child with the highest win rate (max child). This is the
+    /// default.
+    #[default]
+    Max,
+    /// Picks the child with the most visits (robust child).
+    Robust,
+    /// Picks the child with both the highest win rate & the most visits. The
+    /// search never runs past its limit to find one, so if no child has both,
+    /// the child that comes closest on its weaker measure is picked instead.
+    /// Each measure is taken as a fraction of the best of any child, so this
+    /// is the child with the highest `min(visits / most visits, win rate /
+    /// highest win rate)`.
+    MaxRobust,
+    /// Picks the child with the highest lower confidence bound on its win rate
+    /// (secure child), which is `win_rate - A / sqrt(visits)`. Uses the given
+    /// constant `A` or [`SECURE_DEFAULT_CONST`] if none is given.
+    Secure(Option<f64>),
+}
+
+impl FinalMoveStrategy {
+    /// Returns the score of the given root child, where the most visits & the
+    /// highest win rate of any root child are also given. The child with the
+    /// highest score is played. Unvisited children score [`f64::MIN`], which
+    /// is still above the proven losses that are only played as a last
+    /// resort.
+    pub(super) fn score<P, M, ME, S>(
+        &self,
+        child: &Node<P, M, ME, S>,
+        most_visits: u64,
+        best_win_rate: f64,
+    ) -> f64
+    where
+        P: Copy + PartialEq + ToString + fmt::Debug,
+        M: Copy + PartialEq + fmt::Debug,
+        ME: Copy + fmt::Debug,
+        S: GameState<P, M, ME>,
+    {
+        if child.visits == 0 {
+            return f64::MIN;
+        }
+
+        match *self {
+            Self::Max => child.win_rate(),
+            Self::Robust => child.visits as f64,
+            Self::MaxRobust => {
+                // A child that leads on both measures scores 1.
+                let share = |value: f64, best: f64| if best > 0.0 { value / best } else { 1.0 };
+                share(child.visits as f64, most_visits as f64)
+                    .min(share(child.win_rate(), best_win_rate))
+            }
+            Self::Secure(a) => {
+                child.win_rate() - a.unwrap_or(SECURE_DEFAULT_CONST) / (child.visits as f64).sqrt()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connect4::{Game, Move, MoveError, Player};
+    use crate::Mcts;
+
+    /// Returns an AI whose root has a child for each of the given visits &
+    /// wins, in column order.
+    fn ai_with_children(children: &[(u64, f64)]) -> Mcts<Player, Move, MoveError, Game> {
+        let mut ai = Mcts::with_seed(Player::Red, &Game::new(), 0);
+        for (col, &(visits, wins)) in children.iter().enumerate() {
+            let child_id = ai.make_move(ai.cur_node_id, col as Move);
+            let child = ai.get_node_mut(child_id);
+            child.visits = visits;
+            child.wins = wins;
+        }
+        ai
+    }
+
+    /// Returns the move that the given strategy picks.
+    fn pick(ai: &mut Mcts<Player, Move, MoveError, Game>, strategy: FinalMoveStrategy) -> Move {
+        ai.final_move = strategy;
+        ai.phase_action_select()
+    }
+
+    #[test]
+    fn each_strategy_picks_its_own_child() {
+        let mut ai = ai_with_children(&[
+            // The most visits, but a poor win rate.
+            (100, 50.0),
+            // The best win rate, from hardly any visits.
+            (2, 2.0),
+            // Close to the best on both.
+            (80, 56.0),
+            // A slightly better win rate with fewer visits, which gives the
+            // highest lower bound.
+            (60, 45.0),
+        ]);
+        assert_eq!(pick(&mut ai, FinalMoveStrategy::Robust), 0);
+        assert_eq!(pick(&mut ai, FinalMoveStrategy::Max), 1);
+        assert_eq!(pick(&mut ai, FinalMoveStrategy::MaxRobust), 2);
+        assert_eq!(pick(&mut ai, FinalMoveStrategy::Secure(None)), 3);
+    }
+
+    #[test]
+    fn max_robust_picks_a_child_that_leads_on_both() {
+        let mut ai = ai_with_children(&[(100, 50.0), (150, 120.0), (10, 7.0)]);
+        for &strategy in [
+            FinalMoveStrategy::Max,
+            FinalMoveStrategy::Robust,
+            FinalMoveStrategy::MaxRobust,
+        ]
+        .iter()
+        {
+            assert_eq!(pick(&mut ai, strategy), 1);
+        }
+    }
+
+    #[test]
+    fn secure_constant_trades_win_rate_for_visits() {
+        let mut ai = ai_with_children(&[(100, 60.0), (4, 3.0)]);
+        assert_eq!(pick(&mut ai, FinalMoveStrategy::Secure(Some(0.0))), 1);
+        assert_eq!(pick(&mut ai, FinalMoveStrategy::Secure(Some(1.0))), 0);
+    }
+
+    #[test]
+    fn unvisited_children_are_ranked_last() {
+        let mut ai = ai_with_children(&[(0, 0.0), (10, 2.0), (0, 0.0)]);
+        for &strategy in [
+            FinalMoveStrategy::Max,
+            FinalMoveStrategy::Robust,
+            FinalMoveStrategy::MaxRobust,
+            FinalMoveStrategy::Secure(None),
+        ]
+        .iter()
+        {
+            assert_eq!(pick(&mut ai, strategy), 1);
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+/// The limit on how long a single search can run for. The search stops as
+/// soon as the limit is reached.
+///
+/// Every search runs at least one round so that there is a move to select, so
+/// a limit that is reached before the search starts, such as `Rounds(0)`,
+/// still gives one round. An empty `Any` is never reached, so a search using it
+/// only stops once the result of the game from the root is proven.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SearchLimit {
+    /// Stops after the given number of rounds of MCTS.
+    Rounds(u64),
+    /// Stops once the tree contains at least the given number of nodes. This
+    /// counts the nodes kept from earlier searches, so a tree that is reused
+    /// after a move can already be at the limit, in which case the search
+    /// stops after its first round.
+    Nodes(usize),
+    /// Stops once the given amount of wall-clock time has passed.
+    Time(Duration),
+    /// Stops as soon as any of the contained limits are reached.
+    Any(Vec<SearchLimit>),
+}
+
+impl SearchLimit {
+    /// Returns a limit that stops when either this limit or the given limit
+    /// is reached, whichever happens first.
+    pub fn or(self, other: SearchLimit) -> Self {
+        match self {
+            Self::Any(mut limits) => {
+                limits.push(other);
+                Self::Any(limits)
+            }
+            limit => Self::Any(vec![limit, other]),
+        }
+    }
+
+    /// Returns whether the limit has been reached given the current progress
+    /// of the search.
+    pub fn is_reached(&self, rounds: u64, nodes: usize, elapsed: Duration) -> bool {
+        match self {
+            Self::Rounds(max) => rounds >= *max,
+            Self::Nodes(max) => nodes >= *max,
+            Self::Time(max) => elapsed >= *max,
+            Self::Any(limits) => limits
+                .iter()
+                .any(|limit| limit.is_reached(rounds, nodes, elapsed)),
+        }
+    }
+}
+
+impl From<Duration> for SearchLimit {
+    fn from(duration: Duration) -> Self {
+        Self::Time(duration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connect4::{Game, Player};
+    use crate::{Mcts, SelectionPolicy};
+
+    #[test]
+    fn each_limit_checks_its_own_measure() {
+        let second = Duration::from_secs(1);
+        assert!(!SearchLimit::Rounds(10).is_reached(9, 1000, second * 1000));
+        assert!(SearchLimit::Rounds(10).is_reached(10, 0, Duration::ZERO));
+
+        assert!(!SearchLimit::Nodes(10).is_reached(1000, 9, second * 1000));
+        assert!(SearchLimit::Nodes(10).is_reached(0, 10, Duration::ZERO));
+
+        assert!(!SearchLimit::Time(second).is_reached(1000, 1000, second / 2));
+        assert!(SearchLimit::Time(second).is_reached(0, 0, second));
+    }
+
+    #[test]
+    fn any_stops_at_whichever_is_reached_first() {
+        let limit = SearchLimit::Rounds(10).or(SearchLimit::Nodes(5));
+        assert!(!limit.is_reached(9, 4, Duration::ZERO));
+        assert!(limit.is_reached(10, 4, Duration::ZERO));
+        assert!(limit.is_reached(9, 5, Duration::ZERO));
+        assert!(!SearchLimit::Any(vec![]).is_reached(1000, 1000, Duration::ZERO));
+    }
+
+    #[test]
+    fn or_adds_to_an_existing_any() {
+        let limit = SearchLimit::Rounds(10)
+            .or(SearchLimit::Nodes(5))
+            .or(Duration::from_secs(1).into());
+        assert_eq!(
+            limit,
+            SearchLimit::Any(vec![
+                SearchLimit::Rounds(10),
+                SearchLimit::Nodes(5),
+                SearchLimit::Time(Duration::from_secs(1)),
+            ])
+        );
+    }
+
+    #[test]
+    fn searches_run_at_least_one_round() {
+        let game = Game::new();
+        let mut ai = Mcts::with_seed(Player::Red, &game, 0);
+        let report = ai.select_next_move(&SearchLimit::Rounds(0), &SelectionPolicy::Ucb1(None));
+        assert_eq!(report.rounds, 1);
+    }
+
+    #[test]
+    fn node_limit_stops_the_search() {
+        // Each round adds one node to the root, which is already in the tree.
+        let game = Game::new();
+        let mut ai = Mcts::with_seed(Player::Red, &game, 0);
+        let report = ai.select_next_move(&SearchLimit::Nodes(50), &SelectionPolicy::Ucb1(None));
+        assert_eq!(report.rounds, 49);
+        assert_eq!(report.tree_size, 50);
+
+        // The tree kept after the move is already over a smaller limit.
+        ai.update_target_move(report.best_move);
+        let report = ai.select_next_move(&SearchLimit::Nodes(5), &SelectionPolicy::Ucb1(None));
+        assert_eq!(report.rounds, 1);
+        assert!(report.tree_size > 6);
+    }
+}
@@ -0,0 +1,381 @@
+use super::{Mcts, Node, SearchLimit, SearchReport, TreePolicy};
+use crate::game::GameState;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Instant;
+
+/// The way that a parallel search is split across threads.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Parallelism {
+    /// Each thread searches its own independent copy of the tree, and the
+    /// statistics of every node are merged, matching nodes by the moves that
+    /// reach them, once every thread has finished. The search limit applies to
+    /// each tree separately.
+    Root { threads: usize },
+    /// All threads search a single shared tree. Each node on the path of an
+    /// in-progress round has `virtual_loss` extra visits (without wins) added
+    /// to it, so that other threads are steered towards different lines. The
+    /// search limit applies to the shared tree.
+    ///
+    /// The virtual loss must be at least 1.
+    Tree { threads: usize, virtual_loss: u64 },
+}
+
+impl<P, M, ME, S, R> Mcts<P, M, ME, S, R>
+where
+    P: Copy + PartialEq + ToString + fmt::Debug + Send + Sync,
+    M: Copy + PartialEq + fmt::Debug + Send + Sync,
+    ME: Copy + fmt::Debug + Send + Sync,
+    S: GameState<P, M, ME> + Send + Sync,
+    R: Rng + SeedableRng + Send,
+{
+    /// Runs MCTS across multiple threads to select the next best move until
+    /// the search limit is reached. Once this limit is reached, the best move
+    /// is selected using the final move strategy & returned as part of a
+    /// report on the search. The report counts the rounds of every thread.
+    ///
+    /// The RNG of each thread is seeded from the RNG of this object, so a
+    /// seeded root-parallel search with a round or node limit is reproducible.
+    /// Tree-parallel searches depend on thread scheduling, and are not.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the game has ended or a chance event is pending, since there
+    /// is no move to select.
+    pub fn select_next_move_parallel<T>(
+        &mut self,
+        limit: &SearchLimit,
+        selection_pol: &T,
+        parallelism: &Parallelism,
+    ) -> SearchReport<M>
+    where
+        T: TreePolicy<P, M, ME, S> + Sync + ?Sized,
+    {
+        self.assert_can_select();
+        // Prune out nodes we don't need.
+        self.prune_nodes();
+
+        let start = Instant::now();
+        let rounds = match *parallelism {
+            Parallelism::Root { threads } => {
+                self.search_root_parallel(limit, selection_pol, threads)
+            }
+            Parallelism::Tree {
+                threads,
+                virtual_loss,
+            } => self.search_tree_parallel(limit, selection_pol, threads, virtual_loss),
+        };
+
+        self.build_report(self.phase_action_select(), rounds, start.elapsed())
+    }
+
+    // Parallel search fns.
+
+    /// Searches independent copies of the tree on each thread, then merges
+    /// every node of each copy into this tree. Returns the total number of
+    /// rounds performed.
+    fn search_root_parallel<T>(
+        &mut self,
+        limit: &SearchLimit,
+        selection_pol: &T,
+        threads: usize,
+    ) -> u64
+    where
+        T: TreePolicy<P, M, ME, S> + Sync + ?Sized,
+    {
+        assert!(threads > 0, "Parallel search requires at least 1 thread");
+
+        // This object does the work of the first thread, so only the others
+        // need a copy. Each copy starts with the statistics already in the
+        // tree, so the tree is kept as it is now to only merge what the copies
+        // add to it.
+        let mut workers: Vec<Self> = (1..threads).map(|_| self.fork()).collect();
+        let base = if workers.is_empty() {
+            vec![]
+        } else {
+            self.tree.clone()
+        };
+
+        let rounds = thread::scope(|scope| {
+            let handles: Vec<_> = workers
+                .iter_mut()
+                .map(|worker| scope.spawn(move || worker.search(limit, selection_pol)))
+                .collect();
+
+            let own_rounds = self.search(limit, selection_pol);
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .sum::<u64>()
+                + own_rounds
+        });
+
+        for worker in workers.iter() {
+            self.merge_tree(
+                worker,
+                &base,
+                self.cur_node_id,
+                worker.cur_node_id,
+                &mut HashMap::new(),
+            );
+        }
+
+        rounds
+    }
+
+    /// Searches this tree from multiple threads at once, using virtual loss to
+    /// spread the threads out. Returns the total number of rounds performed.
+    fn search_tree_parallel<T>(
+        &mut self,
+        limit: &SearchLimit,
+        selection_pol: &T,
+        threads: usize,
+        virtual_loss: u64,
+    ) -> u64
+    where
+        T: TreePolicy<P, M, ME, S> + Sync + ?Sized,
+    {
+        assert!(threads > 0, "Parallel search requires at least 1 thread");
+        assert!(
+            virtual_loss > 0,
+            "Tree-parallel search requires a virtual loss of at least 1"
+        );
+
+        // Each thread gets its own RNG for rollouts, which happen outside of
+        // the lock.
+        let rngs: Vec<R> = (0..threads)
+            .map(|_| R::from_rng(&mut self.rng).unwrap())
+            .collect();
+
+        let rollout_pol = self.rollout_pol.clone();
+        let start = Instant::now();
+        // The rounds counter is kept inside the lock so that threads agree on
+        // when the limit has been reached.
+        let shared = Mutex::new((self, 0u64));
+
+        thread::scope(|scope| {
+            for mut rng in rngs {
+                let shared = &shared;
+                let rollout_pol = &rollout_pol;
+                scope.spawn(move || loop {
+                    // Selection & expansion need exclusive access to the tree.
+                    let (path, proven, state) = {
+                        let mut guard = shared.lock().unwrap();
+                        let (mcts, rounds) = &mut *guard;
+                        if *rounds > 0
+                            && (limit.is_reached(*rounds, mcts.tree.len(), start.elapsed())
+                                || mcts.get_cur_node().is_proven())
+                        {
+                            break;
+                        }
+                        *rounds += 1;
+
+                        let mut path = mcts.phase_selection(mcts.cur_node_id, selection_pol);
+                        mcts.phase_expansion(&mut path);
+                        mcts.apply_virtual_loss(&path, virtual_loss);
+                        let node = mcts.get_node(*path.last().unwrap());
+                        (path, node.proven.clone(), node.state.clone())
+                    };
+
+                    // Proven nodes don't need rolling out.
+                    let result = proven.unwrap_or_else(|| rollout_pol.rollout(&state, &mut rng));
+
+                    let mut guard = shared.lock().unwrap();
+                    let (mcts, _) = &mut *guard;
+                    mcts.revert_virtual_loss(&path, virtual_loss);
+                    mcts.phase_backprop(&path, &result);
+                });
+            }
+        });
+
+        shared.into_inner().unwrap().1
+    }
+
+    // Parallel helper fns.
+
+    /// Returns a copy of this object that has its own RNG seeded from this
+    /// object's RNG.
+    fn fork(&mut self) -> Self {
+        Mcts {
+            tree: self.tree.clone(),
+            cur_node_id: self.cur_node_id,
+            target_player: self.target_player,
+            rng: R::from_rng(&mut self.rng).unwrap(),
+            rollout_pol: self.rollout_pol.clone(),
+            prior_fn: self.prior_fn.clone(),
+            final_move: self.final_move,
+            draw_value: self.draw_value,
+            transpositions: self.transpositions.clone(),
+        }
+    }
+
+    /// Adds the statistics of the given node of another tree, & of every node
+    /// below it, onto the node of this tree reached by the same moves. Nodes
+    /// that don't exist in this tree yet are created, and nodes proven in the
+    /// other tree are proven in this tree too.
+    ///
+    /// The other tree must have been forked from this one, where the given base
+    /// nodes are the nodes of this tree at the time of the fork. Only the
+    /// statistics gained since then are added, so that they aren't counted
+    /// twice. Nodes shared between paths are only merged once, using the map
+    /// of the nodes of the other tree that have been merged so far.
+    fn merge_tree(
+        &mut self,
+        other: &Self,
+        base: &[Node<P, M, ME, S>],
+        node_id: usize,
+        other_id: usize,
+        merged: &mut HashMap<usize, usize>,
+    ) {
+        if merged.insert(other_id, node_id).is_some() {
+            return;
+        }
+
+        let other_node = other.get_node(other_id);
+        self.get_node_mut(node_id)
+            .merge_stats(other_node, base.get(other_id));
+
+        for &(mv, other_child_id) in other_node.child_nodes.iter() {
+            let child_id = match self
+                .get_node(node_id)
+                .child_nodes
+                .iter()
+                .find(|&&(m, _)| m == mv)
+            {
+                Some(&(_, child_id)) => child_id,
+                None => self.make_move(node_id, mv),
+            };
+            self.merge_tree(other, base, child_id, other_child_id, merged);
+        }
+        self.update_proven(node_id);
+    }
+
+    /// Adds virtual loss to every node on the given path.
+    fn apply_virtual_loss(&mut self, path: &[usize], virtual_loss: u64) {
+        for &node_id in path.iter() {
+            self.get_node_mut(node_id).visits += virtual_loss;
+        }
+    }
+
+    /// Removes virtual loss from every node on the given path.
+    fn revert_virtual_loss(&mut self, path: &[usize], virtual_loss: u64) {
+        for &node_id in path.iter() {
+            self.get_node_mut(node_id).visits -= virtual_loss;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connect4::{Game, Player};
+    use crate::SelectionPolicy;
+
+    #[test]
+    fn root_parallel_search_is_reproducible() {
+        let search = || {
+            let mut ai = Mcts::with_seed(Player::Red, &Game::new(), 7);
+            ai.select_next_move_parallel(
+                &SearchLimit::Rounds(300),
+                &SelectionPolicy::Ucb1(None),
+                &Parallelism::Root { threads: 3 },
+            )
+        };
+        let (first, second) = (search(), search());
+        assert_eq!(first.rounds, 900);
+        assert_eq!(first.best_move, second.best_move);
+        assert_eq!(first.children, second.children);
+    }
+
+    #[test]
+    fn root_parallel_search_merges_root_stats_once() {
+        let mut ai = Mcts::with_seed(Player::Red, &Game::new(), 0);
+        let mut total = 0;
+        for _ in 0..2 {
+            let report = ai.select_next_move_parallel(
+                &SearchLimit::Rounds(200),
+                &SelectionPolicy::Ucb1(None),
+                &Parallelism::Root { threads: 3 },
+            );
+            total += report.rounds;
+
+            // The root is visited once a round, & the stats already in the
+            // tree before the search aren't added again for each thread.
+            let root = ai.get_cur_node();
+            let child_visits: u64 = root
+                .child_nodes
+                .iter()
+                .map(|&(_, id)| ai.get_node(id).visits)
+                .sum();
+            assert_eq!(root.visits, total);
+            assert_eq!(child_visits, total);
+            assert!(root.wins <= total as f64);
+        }
+    }
+
+    #[test]
+    fn root_parallel_search_merges_the_whole_tree() {
+        let mut ai = Mcts::with_seed(Player::Red, &Game::new(), 0);
+        for _ in 0..2 {
+            let report = ai.select_next_move_parallel(
+                &SearchLimit::Rounds(200),
+                &SelectionPolicy::Ucb1(None),
+                &Parallelism::Root { threads: 3 },
+            );
+
+            // Every node holds the visits of every thread, so each unproven
+            // node has the visits of its children plus at most the one visit
+            // that created it in each thread.
+            for node in &ai.tree {
+                let child_visits: u64 = node
+                    .child_nodes
+                    .iter()
+                    .map(|&(_, id)| ai.get_node(id).visits)
+                    .sum();
+                assert!(child_visits <= node.visits);
+                if !node.is_proven() {
+                    assert!(node.visits <= child_visits + 3);
+                }
+            }
+            ai.update_target_move(report.best_move);
+            ai.update_opponent_move(report.best_move);
+        }
+    }
+
+    #[test]
+    fn tree_parallel_search_runs_the_exact_rounds() {
+        let mut ai = Mcts::with_seed(Player::Red, &Game::new(), 0);
+        let report = ai.select_next_move_parallel(
+            &SearchLimit::Rounds(500),
+            &SelectionPolicy::Ucb1(None),
+            &Parallelism::Tree {
+                threads: 4,
+                virtual_loss: 3,
+            },
+        );
+        assert_eq!(report.rounds, 500);
+
+        // Any virtual loss left behind would show up as extra visits.
+        let root = ai.get_cur_node();
+        assert_eq!(root.visits, 500);
+        assert_eq!(
+            report
+                .children
+                .iter()
+                .map(|child| child.visits)
+                .sum::<u64>(),
+            500
+        );
+        for node in &ai.tree {
+            let child_visits: u64 = node
+                .child_nodes
+                .iter()
+                .map(|&(_, id)| ai.get_node(id).visits)
+                .sum();
+            assert!(child_visits <= node.visits);
+        }
+    }
+}
@@ -0,0 +1,438 @@
+use super::{FinalMoveStrategy, Mcts, Node, UniformRandom};
+use crate::game::GameState;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::{error, fmt};
+
+/// The bytes that every saved tree starts with.
+const MAGIC: [u8; 4] = *b"MCTS";
+
+/// The version of the on-disk format written by [`Mcts::save`]. Trees saved
+/// with any other version are rejected when loading.
+pub const FORMAT_VERSION: u32 = 2;
+
+/// The errors possible when saving or loading a search tree.
+#[derive(Debug)]
+pub enum PersistError {
+    /// Reading from or writing to the underlying reader or writer failed.
+    Io(io::Error),
+    /// The tree couldn't be encoded, or the saved data couldn't be decoded.
+    Encoding(bincode::Error),
+    /// The data doesn't start with the expected magic bytes, so it isn't a
+    /// saved tree.
+    NotATree,
+    /// The tree was saved with a different format version.
+    UnsupportedVersion(u32),
+    /// The saved data decoded, but refers to nodes that aren't in the tree,
+    /// has a cycle or has a draw value outside of `[0, 1]`.
+    InvalidTree,
+}
+
+impl fmt::Display for PersistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "I/O error: {}", err),
+            Self::Encoding(err) => write!(f, "Encoding error: {}", err),
+            Self::NotATree => write!(f, "Data is not a saved search tree"),
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "Tree was saved with format version {}, but only version {} is supported",
+                version, FORMAT_VERSION
+            ),
+            Self::InvalidTree => write!(f, "Tree is corrupt"),
+        }
+    }
+}
+
+impl error::Error for PersistError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Encoding(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PersistError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<bincode::Error> for PersistError {
+    fn from(err: bincode::Error) -> Self {
+        Self::Encoding(err)
+    }
+}
+
+/// The part of the saved data that follows the magic bytes & version. The
+/// tree is borrowed when saving & owned when loading.
+#[derive(Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "P: Serialize, M: Serialize, S: Serialize",
+    deserialize = "P: Deserialize<'de>, M: Deserialize<'de>, S: Deserialize<'de>"
+))]
+struct SavedTree<'a, P, M, ME, S>
+where
+    P: Copy + PartialEq + ToString + fmt::Debug,
+    M: Copy + PartialEq + fmt::Debug,
+    ME: Copy + fmt::Debug,
+    S: GameState<P, M, ME>,
+{
+    tree: Cow<'a, [Node<P, M, ME, S>]>,
+    cur_node_id: usize,
+    target_player: P,
+    final_move: FinalMoveStrategy,
+    draw_value: f64,
+    transpositions: bool,
+}
+
+impl<P, M, ME, S> SavedTree<'_, P, M, ME, S>
+where
+    P: Copy + PartialEq + ToString + fmt::Debug,
+    M: Copy + PartialEq + fmt::Debug,
+    ME: Copy + fmt::Debug,
+    S: GameState<P, M, ME>,
+{
+    /// Returns whether the current root node & every parent & child ID refer
+    /// to nodes in the tree, no node can be reached again by following its
+    /// children, & the draw value is one that [`Mcts::with_draw_value`]
+    /// accepts, so that data that decoded but is corrupt isn't loaded.
+    fn is_valid(&self) -> bool {
+        let len = self.tree.len();
+        (0.0..=1.0).contains(&self.draw_value)
+            && self.cur_node_id < len
+            && self.tree.iter().all(|node| {
+                node.parent_nodes.iter().all(|&id| id < len)
+                    && node.child_nodes.iter().all(|&(_, id)| id < len)
+            })
+            && !self.has_cycle()
+    }
+
+    /// Returns whether following the child links from any node leads back to
+    /// that node. Every child ID must refer to a node in the tree. Nodes are
+    /// walked depth first without recursion, since the tree can be deep.
+    fn has_cycle(&self) -> bool {
+        // Nodes start out unvisited, are on the path while their children are
+        // being walked & are done once none of their children lead back.
+        #[derive(Clone, Copy, PartialEq)]
+        enum Mark {
+            Unvisited,
+            OnPath,
+            Done,
+        }
+
+        let mut marks = vec![Mark::Unvisited; self.tree.len()];
+        for start in 0..self.tree.len() {
+            if marks[start] != Mark::Unvisited {
+                continue;
+            }
+
+            // Each entry is a node on the path & the index of the next child
+            // to walk.
+            let mut path = vec![(start, 0)];
+            marks[start] = Mark::OnPath;
+            while let Some((node_id, next)) = path.last_mut() {
+                match self.tree[*node_id].child_nodes.get(*next) {
+                    Some(&(_, child_id)) => {
+                        *next += 1;
+                        match marks[child_id] {
+                            Mark::OnPath => return true,
+                            Mark::Done => {}
+                            Mark::Unvisited => {
+                                marks[child_id] = Mark::OnPath;
+                                path.push((child_id, 0));
+                            }
+                        }
+                    }
+                    None => {
+                        marks[*node_id] = Mark::Done;
+                        path.pop();
+                    }
+                }
+            }
+        }
+
+        false
+    }
+}
+
+impl<P, M, ME, S> Mcts<P, M, ME, S, StdRng>
+where
+    P: Copy + PartialEq + ToString + fmt::Debug + Serialize + DeserializeOwned,
+    M: Copy + PartialEq + fmt::Debug + Serialize + DeserializeOwned,
+    ME: Copy + fmt::Debug,
+    S: GameState<P, M, ME> + Serialize + DeserializeOwned,
+{
+    /// Loads a tree saved with [`Mcts::save`] from the given reader. The RNG
+    /// is seeded from system entropy, so the search is not reproducible.
+    ///
+    /// See [`Mcts::load_with_rng`] for the settings that aren't saved.
+    pub fn load<Rd: Read>(reader: Rd) -> Result<Self, PersistError> {
+        Self::load_with_rng(reader, StdRng::from_entropy())
+    }
+
+    /// Loads a tree saved with [`Mcts::save_to_file`] from the given file.
+    pub fn load_from_file<F: AsRef<Path>>(path: F) -> Result<Self, PersistError> {
+        Self::load(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<P, M, ME, S, R> Mcts<P, M, ME, S, R>
+where
+    P: Copy + PartialEq + ToString + fmt::Debug + Serialize + DeserializeOwned,
+    M: Copy + PartialEq + fmt::Debug + Serialize + DeserializeOwned,
+    ME: Copy + fmt::Debug,
+    S: GameState<P, M, ME> + Serialize + DeserializeOwned,
+    R: Rng,
+{
+    /// Saves the tree, the current root node & the target player to the given
+    /// writer, along with the final move strategy, draw value & whether
+    /// transpositions are enabled. The data starts with a format version, so
+    /// that trees from older versions of the crate are rejected instead of
+    /// being misread.
+    pub fn save<W: Write>(&self, mut writer: W) -> Result<(), PersistError> {
+        writer.write_all(&MAGIC)?;
+        bincode::serialize_into(&mut writer, &FORMAT_VERSION)?;
+
+        let saved = SavedTree {
+            tree: Cow::Borrowed(&self.tree),
+            cur_node_id: self.cur_node_id,
+            target_player: self.target_player,
+            final_move: self.final_move,
+            draw_value: self.draw_value,
+            transpositions: self.transpositions.is_some(),
+        };
+        bincode::serialize_into(&mut writer, &saved)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Saves the tree to the given file, replacing it if it already exists.
+    pub fn save_to_file<F: AsRef<Path>>(&self, path: F) -> Result<(), PersistError> {
+        self.save(BufWriter::new(File::create(path)?))
+    }
+
+    /// Loads a tree saved with [`Mcts::save`] from the given reader, using the
+    /// given RNG for the search.
+    ///
+    /// The rollout policy & prior function can't be saved, so the loaded
+    /// object uses uniformly random rollouts & no priors. These can be set
+    /// again with [`Mcts::with_rollout_policy`] & [`Mcts::with_priors`].
+    pub fn load_with_rng<Rd: Read>(mut reader: Rd, rng: R) -> Result<Self, PersistError> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(PersistError::NotATree);
+        }
+        let version: u32 = bincode::deserialize_from(&mut reader)?;
+        if version != FORMAT_VERSION {
+            return Err(PersistError::UnsupportedVersion(version));
+        }
+
+        let saved: SavedTree<'_, P, M, ME, S> = bincode::deserialize_from(&mut reader)?;
+        if !saved.is_valid() {
+            return Err(PersistError::InvalidTree);
+        }
+        let mut mcts = Mcts {
+            tree: saved.tree.into_owned(),
+            cur_node_id: saved.cur_node_id,
+            target_player: saved.target_player,
+            rng,
+            rollout_pol: Arc::new(UniformRandom),
+            prior_fn: None,
+            final_move: saved.final_move,
+            draw_value: saved.draw_value,
+            transpositions: None,
+        };
+        if saved.transpositions {
+            mcts.transpositions = Some(HashMap::new());
+            mcts.rebuild_transpositions();
+        }
+        Ok(mcts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcts::{SearchLimit, SelectionPolicy};
+    use crate::mnk::{self, Player};
+
+    type TicTacToe = Mcts<Player, mnk::Move, mnk::MoveError, mnk::Game>;
+
+    /// Returns a tic-tac-toe AI that has searched for a few rounds, with
+    /// transpositions enabled.
+    fn searched_ai() -> TicTacToe {
+        let mut ai = Mcts::with_seed(Player::X, &mnk::Game::tic_tac_toe(), 0)
+            .with_transpositions()
+            .with_draw_value(0.25);
+        ai.select_next_move(&SearchLimit::Rounds(200), &SelectionPolicy::Ucb1(None));
+        ai
+    }
+
+    /// Returns the given AI saved to bytes.
+    fn save(ai: &TicTacToe) -> Vec<u8> {
+        let mut bytes = vec![];
+        ai.save(&mut bytes).unwrap();
+        bytes
+    }
+
+    /// Returns the given tree saved to bytes, without any checks on it.
+    fn save_raw(
+        tree: Vec<Node<Player, mnk::Move, mnk::MoveError, mnk::Game>>,
+        cur_node_id: usize,
+    ) -> Vec<u8> {
+        save_raw_with_draw_value(tree, cur_node_id, 0.5)
+    }
+
+    /// Returns the given tree & draw value saved to bytes, without any checks
+    /// on them.
+    fn save_raw_with_draw_value(
+        tree: Vec<Node<Player, mnk::Move, mnk::MoveError, mnk::Game>>,
+        cur_node_id: usize,
+        draw_value: f64,
+    ) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bincode::serialize_into(&mut bytes, &FORMAT_VERSION).unwrap();
+        let saved = SavedTree {
+            tree: Cow::Owned(tree),
+            cur_node_id,
+            target_player: Player::X,
+            final_move: FinalMoveStrategy::Max,
+            draw_value,
+            transpositions: false,
+        };
+        bincode::serialize_into(&mut bytes, &saved).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn round_trips_the_tree_and_settings() {
+        let ai = searched_ai();
+        let loaded = TicTacToe::load_with_rng(&save(&ai)[..], StdRng::seed_from_u64(0)).unwrap();
+
+        assert_eq!(loaded.tree.len(), ai.tree.len());
+        assert_eq!(loaded.cur_node_id, ai.cur_node_id);
+        assert_eq!(loaded.target_player, ai.target_player);
+        assert_eq!(loaded.final_move, ai.final_move);
+        assert_eq!(loaded.draw_value, ai.draw_value);
+        assert_eq!(loaded.transpositions, ai.transpositions);
+        for (a, b) in ai.tree.iter().zip(loaded.tree.iter()) {
+            assert_eq!(a.child_nodes, b.child_nodes);
+            assert_eq!(a.parent_nodes, b.parent_nodes);
+            assert_eq!(a.visits, b.visits);
+            assert_eq!(a.wins, b.wins);
+            assert_eq!(a.proven, b.proven);
+            assert_eq!(a.state, b.state);
+        }
+    }
+
+    #[test]
+    fn loaded_tree_can_keep_searching() {
+        let mut loaded = TicTacToe::load(&save(&searched_ai())[..]).unwrap();
+        let visits = loaded.get_cur_node().visits;
+        let report =
+            loaded.select_next_move(&SearchLimit::Rounds(100), &SelectionPolicy::Ucb1(None));
+        assert_eq!(loaded.get_cur_node().visits, visits + report.rounds);
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let mut bytes = save(&searched_ai());
+        bytes[MAGIC.len()..MAGIC.len() + 4].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            TicTacToe::load(&bytes[..]),
+            Err(PersistError::UnsupportedVersion(version)) if version == FORMAT_VERSION + 1
+        ));
+
+        bytes[0] = b'X';
+        assert!(matches!(
+            TicTacToe::load(&bytes[..]),
+            Err(PersistError::NotATree)
+        ));
+    }
+
+    #[test]
+    fn rejects_truncated_data() {
+        let bytes = save(&searched_ai());
+        for &len in [2, MAGIC.len() + 2, bytes.len() / 2, bytes.len() - 1].iter() {
+            assert!(matches!(
+                TicTacToe::load(&bytes[..len]),
+                Err(PersistError::Io(_)) | Err(PersistError::Encoding(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn rejects_ids_outside_of_the_tree() {
+        let ai = searched_ai();
+        let len = ai.tree.len();
+        assert!(TicTacToe::load(&save_raw(ai.tree.clone(), 0)[..]).is_ok());
+        assert!(matches!(
+            TicTacToe::load(&save_raw(ai.tree.clone(), len)[..]),
+            Err(PersistError::InvalidTree)
+        ));
+
+        let mut tree = ai.tree.clone();
+        tree[0].child_nodes[0].1 = len;
+        assert!(matches!(
+            TicTacToe::load(&save_raw(tree, 0)[..]),
+            Err(PersistError::InvalidTree)
+        ));
+
+        let mut tree = ai.tree.clone();
+        tree[1].parent_nodes.push(len + 10);
+        assert!(matches!(
+            TicTacToe::load(&save_raw(tree, 0)[..]),
+            Err(PersistError::InvalidTree)
+        ));
+    }
+
+    #[test]
+    fn rejects_cyclic_trees() {
+        // Nodes shared through the transposition table aren't cycles.
+        let ai = searched_ai();
+        assert!(ai.tree.iter().any(|node| node.parent_nodes.len() > 1));
+        assert!(TicTacToe::load(&save(&ai)[..]).is_ok());
+        let mv = ai.tree[0].child_nodes[0].0;
+
+        let mut tree = ai.tree.clone();
+        let (child_id, grandchild_id) = (1..tree.len())
+            .find_map(|id| tree[id].child_nodes.first().map(|&(_, child)| (id, child)))
+            .unwrap();
+        tree[grandchild_id].child_nodes.push((mv, child_id));
+        assert!(matches!(
+            TicTacToe::load(&save_raw(tree, 0)[..]),
+            Err(PersistError::InvalidTree)
+        ));
+
+        let mut tree = ai.tree.clone();
+        tree[0].child_nodes.push((mv, 0));
+        assert!(matches!(
+            TicTacToe::load(&save_raw(tree, 0)[..]),
+            Err(PersistError::InvalidTree)
+        ));
+    }
+
+    #[test]
+    fn rejects_draw_values_outside_of_0_to_1() {
+        let ai = searched_ai();
+        assert!(TicTacToe::load(&save_raw_with_draw_value(ai.tree.clone(), 0, 1.0)[..]).is_ok());
+        for &draw_value in [-1.0, 1.5, f64::NAN].iter() {
+            assert!(matches!(
+                TicTacToe::load(&save_raw_with_draw_value(ai.tree.clone(), 0, draw_value)[..]),
+                Err(PersistError::InvalidTree)
+            ));
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use super::{Mcts, Node};
+use crate::game::GameState;
+use rand::Rng;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+/// The statistics of a single child of the root node.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChildReport<M> {
+    /// The move that leads to the child.
+    pub mv: M,
+    /// The number of times the child was visited.
+    pub visits: u64,
+    /// The total reward the player making the move received from the child,
+    /// where draws count for the draw value.
+    pub wins: f64,
+    /// The average reward of the child.
+    pub win_rate: f64,
+}
+
+/// A summary of a single search, describing why the selected move was chosen.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchReport<M> {
+    /// The move that was selected.
+    pub best_move: M,
+    /// The number of rounds of MCTS that were performed.
+    pub rounds: u64,
+    /// The statistics of every child of the root node, in the order they were
+    /// expanded.
+    pub children: Vec<ChildReport<M>>,
+    /// The best line found, starting with the selected move. After the first
+    /// move, the most visited child is followed at each step.
+    pub principal_variation: Vec<M>,
+    /// The number of nodes in the tree.
+    pub tree_size: usize,
+    /// The depth of the deepest node below the root node.
+    pub max_depth: usize,
+    /// The wall-clock time that the search took.
+    pub elapsed: Duration,
+}
+
+impl<M> SearchReport<M> {
+    /// Returns the number of rounds performed per second of the search.
+    pub fn rounds_per_sec(&self) -> f64 {
+        self.rounds as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
+impl<M: fmt::Debug> fmt::Display for SearchReport<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Selected {:?} after {} rounds of MCTS in {:.3}s ({:.0} rounds/s)",
+            self.best_move,
+            self.rounds,
+            self.elapsed.as_secs_f64(),
+            self.rounds_per_sec()
+        )?;
+        writeln!(
+            f,
+            "Tree size: {} nodes, max depth: {}",
+            self.tree_size, self.max_depth
+        )?;
+        writeln!(f, "Principal variation: {:?}", self.principal_variation)?;
+        for (i, child) in self.children.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(
+                f,
+                "  {:?}: {} visits, {:.1} wins, {:.1}% win rate",
+                child.mv,
+                child.visits,
+                child.wins,
+                child.win_rate * 100.0
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<P, M, ME, S, R> Mcts<P, M, ME, S, R>
+where
+    P: Copy + PartialEq + ToString + fmt::Debug,
+    M: Copy + PartialEq + fmt::Debug,
+    ME: Copy + fmt::Debug,
+    S: GameState<P, M, ME>,
+    R: Rng,
+{
+    /// Builds the report of a search that has just selected the given move
+    /// from the current root node.
+    pub(super) fn build_report(
+        &self,
+        best_move: M,
+        rounds: u64,
+        elapsed: Duration,
+    ) -> SearchReport<M> {
+        let root = self.get_cur_node();
+
+        SearchReport {
+            best_move,
+            rounds,
+            children: root
+                .child_nodes
+                .iter()
+                .map(|&(mv, child_id)| {
+                    let child = self.get_node(child_id);
+                    ChildReport {
+                        mv,
+                        visits: child.visits,
+                        wins: child.wins,
+                        win_rate: child.win_rate(),
+                    }
+                })
+                .collect(),
+            principal_variation: self.principal_variation(best_move),
+            tree_size: self.tree.len(),
+            max_depth: self.max_depth(self.cur_node_id),
+            elapsed,
+        }
+    }
+
+    // Report helper fns.
+
+    /// Returns the best line from the current root node, starting with the
+    /// given move & then following the most visited child.
+    fn principal_variation(&self, best_move: M) -> Vec<M> {
+        let mut pv = vec![best_move];
+        let mut node = self
+            .get_cur_node()
+            .child_nodes
+            .iter()
+            .find(|&&(mv, _)| mv == best_move)
+            .map(|&(_, child_id)| self.get_node(child_id));
+
+        while let Some(current) = node {
+            node = current
+                .child_nodes
+                .iter()
+                .max_by_key(|&&(_, child_id)| self.get_node(child_id).visits)
+                .map(|&(mv, child_id)| {
+                    pv.push(mv);
+                    self.get_node(child_id)
+                });
+        }
+
+        pv
+    }
+
+    /// Returns the depth of the deepest descendant of the given node.
+    fn max_depth(&self, node_id: usize) -> usize {
+        self.max_depth_memo(node_id, &mut HashMap::new())
+    }
+
+    /// Returns the depth of the deepest descendant of the given node, storing
+    /// the depth of each node visited so that nodes with several parents are
+    /// only walked once.
+    fn max_depth_memo(&self, node_id: usize, depths: &mut HashMap<usize, usize>) -> usize {
+        if let Some(&depth) = depths.get(&node_id) {
+            return depth;
+        }
+
+        let node: &Node<P, M, ME, S> = self.get_node(node_id);
+        let depth = node
+            .child_nodes
+            .iter()
+            .map(|&(_, child_id)| self.max_depth_memo(child_id, depths) + 1)
+            .max()
+            .unwrap_or(0);
+        depths.insert(node_id, depth);
+        depth
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::connect4::{Game, Move, MoveError, Player};
+    use crate::{Mcts, SearchLimit, SelectionPolicy};
+    use std::time::Duration;
+
+    /// Returns an AI with a tree built by hand from the given lines, where
+    /// each node on a line is visited once per line through it. Lines are
+    /// given as the moves from the root.
+    fn ai_with_lines(lines: &[&[Move]]) -> Mcts<Player, Move, MoveError, Game> {
+        let mut ai = Mcts::with_seed(Player::Red, &Game::new(), 0);
+        for line in lines {
+            let mut node_id = ai.cur_node_id;
+            ai.get_node_mut(node_id).visits += 1;
+            for &mv in line.iter() {
+                let child = ai
+                    .get_node(node_id)
+                    .child_nodes
+                    .iter()
+                    .find(|&&(m, _)| m == mv)
+                    .map(|&(_, child_id)| child_id);
+                node_id = match child {
+                    Some(child_id) => child_id,
+                    None => ai.make_move(node_id, mv),
+                };
+                ai.get_node_mut(node_id).visits += 1;
+            }
+        }
+        ai
+    }
+
+    #[test]
+    fn principal_variation_follows_the_most_visited_children() {
+        let ai = ai_with_lines(&[&[0, 2], &[0, 3, 4], &[0, 3, 5], &[0, 3, 5], &[1, 6, 6, 6]]);
+        let report = ai.build_report(0, 5, Duration::from_secs(1));
+        assert_eq!(report.principal_variation, [0, 3, 5]);
+
+        // The selected move always starts the line, even if it isn't the most
+        // visited child of the root.
+        let report = ai.build_report(1, 5, Duration::from_secs(1));
+        assert_eq!(report.principal_variation, [1, 6, 6, 6]);
+    }
+
+    #[test]
+    fn max_depth_is_the_deepest_line() {
+        let ai = ai_with_lines(&[&[0, 2], &[0, 3, 4], &[1, 6, 6, 6]]);
+        let report = ai.build_report(0, 3, Duration::from_secs(1));
+        assert_eq!(report.max_depth, 4);
+        assert_eq!(report.tree_size, 9);
+
+        let ai = ai_with_lines(&[]);
+        assert_eq!(ai.build_report(0, 0, Duration::from_secs(1)).max_depth, 0);
+    }
+
+    #[test]
+    fn searches_report_a_consistent_line() {
+        let mut ai = Mcts::with_seed(Player::Red, &Game::new(), 0);
+        let report = ai.select_next_move(&SearchLimit::Rounds(500), &SelectionPolicy::Ucb1(None));
+        assert_eq!(report.principal_variation[0], report.best_move);
+        assert!(report.principal_variation.len() <= report.max_depth);
+        assert!(report.max_depth > 1);
+    }
+}
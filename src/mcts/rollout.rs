@@ -0,0 +1,362 @@
+use crate::game::GameState;
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore};
+use std::fmt;
+
+/// The result of a single rollout.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "persist", derive(serde::Serialize, serde::Deserialize))]
+pub enum RolloutResult<P> {
+    /// The rollout played the game to completion & ended with the given
+    /// winner, or `None` if there was no winner.
+    Winner(Option<P>),
+    /// The rollout played the game to completion & ended with the given reward
+    /// for each player. Players that are missing get a reward of 0.
+    Rewards(Vec<(P, f64)>),
+    /// The rollout was cut short & the state it stopped at was given a value
+    /// in `[0, 1]` from the point of view of the given player. 1 means that the
+    /// player is certain to win.
+    ///
+    /// This assumes a two-player game, so every other player gets a value of
+    /// `1 - value`.
+    Value(P, f64),
+}
+
+impl<P> RolloutResult<P>
+where
+    P: Copy + PartialEq + ToString + fmt::Debug,
+{
+    /// Returns the result of a game that has ended in the given state. The
+    /// rewards of the state are used if it has any, otherwise the winner is.
+    pub fn from_terminal<M, ME, S>(state: &S) -> Self
+    where
+        M: Copy + PartialEq + fmt::Debug,
+        ME: Copy + fmt::Debug,
+        S: GameState<P, M, ME>,
+    {
+        match state.get_rewards() {
+            Some(rewards) => Self::Rewards(rewards),
+            None => Self::Winner(state.get_winner()),
+        }
+    }
+
+    /// Returns the reward of the given player, where a win is worth 1, a loss
+    /// 0 & a draw the given draw value.
+    pub fn reward_for(&self, player: P, draw_value: f64) -> f64 {
+        match self {
+            Self::Winner(Some(wnr)) if *wnr == player => 1.0,
+            Self::Winner(Some(_)) => 0.0,
+            Self::Winner(None) => draw_value,
+            Self::Rewards(rewards) => rewards
+                .iter()
+                .find(|(ply, _)| *ply == player)
+                .map_or(0.0, |&(_, reward)| reward),
+            Self::Value(ply, value) if *ply == player => *value,
+            Self::Value(_, value) => 1.0 - value,
+        }
+    }
+}
+
+/// The policy used to play out a game during the rollout phase of MCTS.
+pub trait RolloutPolicy<P, M, ME, S>
+where
+    P: Copy + PartialEq + ToString + fmt::Debug,
+    M: Copy + PartialEq + fmt::Debug,
+    ME: Copy + fmt::Debug,
+    S: GameState<P, M, ME>,
+{
+    /// Plays out the game from the given state & returns the result. All
+    /// randomness should come from the given RNG so that searches stay
+    /// reproducible.
+    fn rollout(&self, state: &S, rng: &mut dyn RngCore) -> RolloutResult<P>;
+}
+
+/// Returns a random move of the given state. If a chance event is pending, the
+/// outcome is sampled by its probability, otherwise each move is equally
+/// likely. Returns `None` if the game has ended.
+fn random_move<P, M, ME, S>(state: &S, rng: &mut dyn RngCore) -> Option<M>
+where
+    P: Copy + PartialEq + ToString + fmt::Debug,
+    M: Copy + PartialEq + fmt::Debug,
+    ME: Copy + fmt::Debug,
+    S: GameState<P, M, ME>,
+{
+    match state.get_chance_outcomes() {
+        Some(outcomes) => outcomes
+            .choose_weighted(rng, |&(_, probability)| probability)
+            .ok()
+            .map(|&(mv, _)| mv),
+        None => state.get_moves().choose(rng).copied(),
+    }
+}
+
+/// Plays uniformly random moves until the game ends. Chance events are
+/// sampled by the probability of each outcome.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UniformRandom;
+
+impl<P, M, ME, S> RolloutPolicy<P, M, ME, S> for UniformRandom
+where
+    P: Copy + PartialEq + ToString + fmt::Debug,
+    M: Copy + PartialEq + fmt::Debug,
+    ME: Copy + fmt::Debug,
+    S: GameState<P, M, ME>,
+{
+    fn rollout(&self, state: &S, rng: &mut dyn RngCore) -> RolloutResult<P> {
+        let mut working_state = state.clone();
+        while let Some(mv) = random_move(&working_state, rng) {
+            working_state.make_move(mv).unwrap();
+        }
+
+        RolloutResult::from_terminal(&working_state)
+    }
+}
+
+/// Plays the move that scores highest on a heuristic until the game ends.
+/// With a probability of `epsilon`, a uniformly random move is played instead.
+/// The heuristic isn't used for chance events, which are sampled by the
+/// probability of each outcome.
+///
+/// The heuristic is given the state before the move & the move itself, and
+/// higher scores are better for the player making the move. Moves scored as
+/// NaN are never picked by the heuristic, & if every move is scored as NaN, a
+/// random move is played.
+pub struct EpsilonGreedy<F> {
+    epsilon: f64,
+    heuristic: F,
+}
+
+impl<F> EpsilonGreedy<F> {
+    /// Constructs a new epsilon-greedy policy using the given chance of playing
+    /// a random move & the heuristic used for all other moves.
+    ///
+    /// # Panics
+    ///
+    /// Panics if epsilon isn't in `[0, 1]`.
+    pub fn new(epsilon: f64, heuristic: F) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&epsilon),
+            "Epsilon must be in [0, 1], but was {}",
+            epsilon
+        );
+        EpsilonGreedy { epsilon, heuristic }
+    }
+}
+
+impl<P, M, ME, S, F> RolloutPolicy<P, M, ME, S> for EpsilonGreedy<F>
+where
+    P: Copy + PartialEq + ToString + fmt::Debug,
+    M: Copy + PartialEq + fmt::Debug,
+    ME: Copy + fmt::Debug,
+    S: GameState<P, M, ME>,
+    F: Fn(&S, M) -> f64,
+{
+    fn rollout(&self, state: &S, rng: &mut dyn RngCore) -> RolloutResult<P> {
+        let mut working_state = state.clone();
+        loop {
+            if working_state.get_chance_outcomes().is_some() {
+                let mv = random_move(&working_state, rng).unwrap();
+                working_state.make_move(mv).unwrap();
+                continue;
+            }
+
+            let moves = working_state.get_moves();
+            if moves.is_empty() {
+                break;
+            }
+
+            let mut scored = if rng.gen_bool(self.epsilon) {
+                vec![]
+            } else {
+                moves
+                    .iter()
+                    .map(|&mv| (mv, (self.heuristic)(&working_state, mv)))
+                    .filter(|(_, score)| !score.is_nan())
+                    .collect::<Vec<(M, f64)>>()
+            };
+            // Reverse the moves so that the first move wins ties.
+            scored.reverse();
+            scored.sort_by(|(_, x), (_, y)| x.total_cmp(y));
+            let mv = match scored.last() {
+                Some(&(mv, _)) => mv,
+                None => *moves.choose(rng).unwrap(),
+            };
+
+            working_state.make_move(mv).unwrap();
+        }
+
+        RolloutResult::from_terminal(&working_state)
+    }
+}
+
+/// Plays uniformly random moves for at most `depth` moves. If the game hasn't
+/// ended by then, the evaluation function is used to value the state that was
+/// reached. Chance events are sampled by the probability of each outcome &
+/// count towards the depth.
+///
+/// The evaluation function returns a value in `[0, 1]` from the point of view
+/// of the current player of the state it is given. The other player gets a
+/// value of `1 - value`, so this policy only works with two-player games.
+///
+/// # Panics
+///
+/// Rollouts panic if a state that is cut short doesn't have exactly two
+/// players.
+pub struct DepthLimited<F> {
+    depth: usize,
+    evaluator: F,
+}
+
+impl<F> DepthLimited<F> {
+    /// Constructs a new depth-limited policy using the given maximum depth &
+    /// evaluation function.
+    pub fn new(depth: usize, evaluator: F) -> Self {
+        DepthLimited { depth, evaluator }
+    }
+}
+
+impl<P, M, ME, S, F> RolloutPolicy<P, M, ME, S> for DepthLimited<F>
+where
+    P: Copy + PartialEq + ToString + fmt::Debug,
+    M: Copy + PartialEq + fmt::Debug,
+    ME: Copy + fmt::Debug,
+    S: GameState<P, M, ME>,
+    F: Fn(&S) -> f64,
+{
+    fn rollout(&self, state: &S, rng: &mut dyn RngCore) -> RolloutResult<P> {
+        let mut working_state = state.clone();
+        for _ in 0..self.depth {
+            match random_move(&working_state, rng) {
+                Some(mv) => working_state.make_move(mv).unwrap(),
+                None => return RolloutResult::from_terminal(&working_state),
+            }
+        }
+
+        if working_state.get_moves().is_empty() {
+            RolloutResult::from_terminal(&working_state)
+        } else {
+            assert_eq!(
+                working_state.get_players().len(),
+                2,
+                "Depth-limited rollouts only value two-player games"
+            );
+            RolloutResult::Value(
+                working_state.get_current_player(),
+                (self.evaluator)(&working_state),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connect4;
+    use crate::mnk::{self, Player};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// Scores earlier cells higher, so that greedy play fills the board in
+    /// order.
+    fn first_cell(_: &mnk::Game, mv: mnk::Move) -> f64 {
+        -f64::from(mv.row * 3 + mv.col)
+    }
+
+    #[test]
+    fn depth_limited_values_the_state_it_stops_at() {
+        let game = connect4::Game::new();
+        let mut rng = StdRng::seed_from_u64(0);
+        let policy = DepthLimited::new(0, |_: &connect4::Game| 0.25);
+        assert_eq!(
+            policy.rollout(&game, &mut rng),
+            RolloutResult::Value(connect4::Player::Red, 0.25)
+        );
+
+        // The value is given from the point of view of the player to move once
+        // the depth is reached.
+        let policy = DepthLimited::new(3, |state: &connect4::Game| {
+            assert_eq!(state.get_moves().len(), connect4::DEFAULT_WIDTH);
+            0.75
+        });
+        let result = policy.rollout(&game, &mut rng);
+        assert_eq!(result, RolloutResult::Value(connect4::Player::Yellow, 0.75));
+        assert_eq!(result.reward_for(connect4::Player::Red, 0.5), 0.25);
+    }
+
+    #[test]
+    fn depth_limited_plays_out_short_games() {
+        let policy = DepthLimited::new(100, |_: &mnk::Game| -> f64 {
+            panic!("Finished games shouldn't be evaluated")
+        });
+        for seed in 0..10 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let result = policy.rollout(&mnk::Game::tic_tac_toe(), &mut rng);
+            assert!(matches!(result, RolloutResult::Winner(_)));
+        }
+    }
+
+    #[test]
+    fn epsilon_greedy_without_epsilon_follows_the_heuristic() {
+        // X takes a1, c1, b2 & a3, which wins on the anti-diagonal.
+        let policy = EpsilonGreedy::new(0.0, first_cell);
+        for seed in 0..10 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            assert_eq!(
+                policy.rollout(&mnk::Game::tic_tac_toe(), &mut rng),
+                RolloutResult::Winner(Some(Player::X))
+            );
+        }
+    }
+
+    #[test]
+    fn epsilon_greedy_with_full_epsilon_plays_randomly() {
+        let policy = EpsilonGreedy::new(1.0, |_: &mnk::Game, _: mnk::Move| -> f64 {
+            panic!("The heuristic shouldn't be used")
+        });
+        let results: Vec<_> = (0..20)
+            .map(|seed| {
+                let mut rng = StdRng::seed_from_u64(seed);
+                policy.rollout(&mnk::Game::tic_tac_toe(), &mut rng)
+            })
+            .collect();
+        assert!(results.iter().any(|result| *result != results[0]));
+    }
+
+    #[test]
+    fn epsilon_greedy_skips_moves_scored_as_nan() {
+        // Scoring every other cell as NaN leaves the corners & the centre, so
+        // X takes a1, b2 & c3 while O takes c1 & a3.
+        let policy = EpsilonGreedy::new(0.0, |state: &mnk::Game, mv: mnk::Move| {
+            if (mv.row * 3 + mv.col) % 2 == 1 {
+                f64::NAN
+            } else {
+                first_cell(state, mv)
+            }
+        });
+        let mut rng = StdRng::seed_from_u64(0);
+        assert_eq!(
+            policy.rollout(&mnk::Game::tic_tac_toe(), &mut rng),
+            RolloutResult::Winner(Some(Player::X))
+        );
+
+        // With every move scored as NaN, random moves are played instead.
+        let policy = EpsilonGreedy::new(0.0, |_: &mnk::Game, _: mnk::Move| f64::NAN);
+        for seed in 0..10 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let result = policy.rollout(&mnk::Game::tic_tac_toe(), &mut rng);
+            assert!(matches!(result, RolloutResult::Winner(_)));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Epsilon must be in [0, 1]")]
+    fn epsilon_greedy_rejects_epsilon_above_1() {
+        EpsilonGreedy::new(1.5, first_cell);
+    }
+
+    #[test]
+    #[should_panic(expected = "Epsilon must be in [0, 1]")]
+    fn epsilon_greedy_rejects_negative_epsilon() {
+        EpsilonGreedy::new(-0.1, first_cell);
+    }
+}
@@ -0,0 +1,280 @@
+use super::Node;
+use crate::game::GameState;
+use rand::RngCore;
+use rand_distr::{Beta, Distribution};
+use std::f64::consts::SQRT_2;
+use std::fmt;
+
+/// Default UCB1 exploration constant. Equals sqrt(2).
+pub const UCB1_DEFAULT_EXPLORE_CONST: f64 = SQRT_2;
+/// Default PUCT exploration constant.
+pub const PUCT_DEFAULT_EXPLORE_CONST: f64 = 1.25;
+
+/// A tree policy that scores the children of a node during the selection phase
+/// of MCTS. The child with the highest score is descended into. Scores are
+/// compared with [`f64::total_cmp`], so a NaN score ranks above every other
+/// score.
+pub trait TreePolicy<P, M, ME, S>
+where
+    P: Copy + PartialEq + ToString + fmt::Debug,
+    M: Copy + PartialEq + fmt::Debug,
+    ME: Copy + fmt::Debug,
+    S: GameState<P, M, ME>,
+{
+    /// Returns the score of the given child of the given parent node. The
+    /// child is always scored from the point of view of the player that moved
+    /// into it, and will have been visited at least once.
+    fn score(
+        &self,
+        parent: &Node<P, M, ME, S>,
+        child: &Node<P, M, ME, S>,
+        rng: &mut dyn RngCore,
+    ) -> f64;
+}
+
+/// The built-in policies used to pick which child to descend into during the
+/// selection phase of MCTS.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SelectionPolicy {
+    /// The standard UCB1 formula, using the given exploration constant or
+    /// [`UCB1_DEFAULT_EXPLORE_CONST`] if none is given.
+    Ucb1(Option<f64>),
+    /// The UCB1-Tuned formula, which scales exploration by an upper bound on
+    /// the variance of each child's rewards.
+    Ucb1Tuned,
+    /// The PUCT formula used by AlphaZero, which weights exploration by the
+    /// prior of each move. Uses the given exploration constant or
+    /// [`PUCT_DEFAULT_EXPLORE_CONST`] if none is given.
+    Puct(Option<f64>),
+    /// Thompson sampling, which samples a win rate for each child from a Beta
+    /// distribution over its wins & losses. Mean rewards outside of `[0, 1]`
+    /// are clamped into it.
+    Thompson,
+}
+
+impl<P, M, ME, S> TreePolicy<P, M, ME, S> for SelectionPolicy
+where
+    P: Copy + PartialEq + ToString + fmt::Debug,
+    M: Copy + PartialEq + fmt::Debug,
+    ME: Copy + fmt::Debug,
+    S: GameState<P, M, ME>,
+{
+    fn score(
+        &self,
+        parent: &Node<P, M, ME, S>,
+        child: &Node<P, M, ME, S>,
+        rng: &mut dyn RngCore,
+    ) -> f64 {
+        match *self {
+            Self::Ucb1(expl) => {
+                selector_ucb1(parent, child, expl.unwrap_or(UCB1_DEFAULT_EXPLORE_CONST))
+            }
+            Self::Ucb1Tuned => selector_ucb1_tuned(parent, child),
+            Self::Puct(expl) => {
+                selector_puct(parent, child, expl.unwrap_or(PUCT_DEFAULT_EXPLORE_CONST))
+            }
+            Self::Thompson => selector_thompson(child, rng),
+        }
+    }
+}
+
+// Selector fns.
+
+/// The standard UCB1 selector function.
+fn selector_ucb1<P, M, ME, S>(
+    node: &Node<P, M, ME, S>,
+    child: &Node<P, M, ME, S>,
+    explore_const: f64,
+) -> f64
+where
+    P: Copy + PartialEq + ToString + fmt::Debug,
+    M: Copy + PartialEq + fmt::Debug,
+    ME: Copy + fmt::Debug,
+    S: GameState<P, M, ME>,
+{
+    child.win_rate() + explore_const * ((node.visits as f64).ln() / (child.visits as f64)).sqrt()
+}
+
+/// The UCB1-Tuned selector function.
+fn selector_ucb1_tuned<P, M, ME, S>(node: &Node<P, M, ME, S>, child: &Node<P, M, ME, S>) -> f64
+where
+    P: Copy + PartialEq + ToString + fmt::Debug,
+    M: Copy + PartialEq + fmt::Debug,
+    ME: Copy + fmt::Debug,
+    S: GameState<P, M, ME>,
+{
+    let log_ratio = (node.visits as f64).ln() / (child.visits as f64);
+    let mean = child.win_rate();
+    let variance_bound =
+        child.wins_sq / (child.visits as f64) - mean * mean + (2.0 * log_ratio).sqrt();
+
+    mean + (log_ratio * variance_bound.min(0.25)).sqrt()
+}
+
+/// The PUCT selector function.
+fn selector_puct<P, M, ME, S>(
+    node: &Node<P, M, ME, S>,
+    child: &Node<P, M, ME, S>,
+    explore_const: f64,
+) -> f64
+where
+    P: Copy + PartialEq + ToString + fmt::Debug,
+    M: Copy + PartialEq + fmt::Debug,
+    ME: Copy + fmt::Debug,
+    S: GameState<P, M, ME>,
+{
+    child.win_rate()
+        + explore_const * child.prior * (node.visits as f64).sqrt() / (1.0 + child.visits as f64)
+}
+
+/// The Thompson sampling selector function.
+fn selector_thompson<P, M, ME, S>(child: &Node<P, M, ME, S>, rng: &mut dyn RngCore) -> f64
+where
+    P: Copy + PartialEq + ToString + fmt::Debug,
+    M: Copy + PartialEq + fmt::Debug,
+    ME: Copy + fmt::Debug,
+    S: GameState<P, M, ME>,
+{
+    // Rewards can fall outside of [0, 1], so the wins & losses are rebuilt
+    // from the clamped mean to keep both Beta parameters positive.
+    let visits = child.visits as f64;
+    let mean = if child.visits > 0 {
+        (child.wins / visits).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    Beta::new(mean * visits + 1.0, (1.0 - mean) * visits + 1.0)
+        .unwrap()
+        .sample(rng)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connect4::{Game, Move, MoveError, Player};
+    use crate::mcts::RolloutResult;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    type TestNode = Node<Player, Move, MoveError, Game>;
+
+    /// Returns a node with the given stats, where each reward added to the
+    /// wins is the same.
+    fn node(visits: u64, wins: f64, prior: f64) -> TestNode {
+        let mut node = Node::new(Some(0), Player::Red, None, Game::new(), prior);
+        node.visits = visits;
+        node.wins = wins;
+        node.wins_sq = if visits > 0 {
+            wins * wins / visits as f64
+        } else {
+            0.0
+        };
+        node
+    }
+
+    /// Returns the score of the child under the given policy.
+    fn score(policy: SelectionPolicy, parent: &TestNode, child: &TestNode) -> f64 {
+        policy.score(parent, child, &mut StdRng::seed_from_u64(0))
+    }
+
+    #[test]
+    fn ucb1_tuned_caps_the_variance_bound() {
+        // Children with very few visits have the bound capped at 1/4, the
+        // largest variance possible for rewards in [0, 1].
+        let parent = node(100, 50.0, 1.0);
+        let child = node(10, 6.0, 1.0);
+        let tuned = score(SelectionPolicy::Ucb1Tuned, &parent, &child);
+        assert!((tuned - 0.939_307).abs() < 1e-6, "score was {}", tuned);
+    }
+
+    #[test]
+    fn ucb1_tuned_explores_less_when_rewards_dont_vary() {
+        // Every rollout from the child was a draw, so its rewards have no
+        // variance & it gets far less exploration than UCB1 would give it.
+        let parent = node(1000, 500.0, 1.0);
+        let child = node(1000, 500.0, 1.0);
+        let tuned = score(SelectionPolicy::Ucb1Tuned, &parent, &child);
+        assert!((tuned - 0.528_494).abs() < 1e-6, "score was {}", tuned);
+        assert!(tuned < score(SelectionPolicy::Ucb1(None), &parent, &child));
+    }
+
+    #[test]
+    fn puct_weights_exploration_by_the_prior() {
+        let parent = node(16, 8.0, 1.0);
+        let child = node(3, 1.5, 0.5);
+        // 0.5 + 1.25 * 0.5 * sqrt(16) / (1 + 3)
+        assert_eq!(score(SelectionPolicy::Puct(None), &parent, &child), 1.125);
+        assert_eq!(
+            score(SelectionPolicy::Puct(Some(2.0)), &parent, &child),
+            1.5
+        );
+
+        // Without a prior, only the win rate counts.
+        let unlikely = node(3, 1.5, 0.0);
+        assert_eq!(score(SelectionPolicy::Puct(None), &parent, &unlikely), 0.5);
+    }
+
+    #[test]
+    fn thompson_samples_around_the_win_rate() {
+        let parent = node(1000, 500.0, 1.0);
+        let child = node(1000, 900.0, 1.0);
+        let mut rng = StdRng::seed_from_u64(0);
+        let samples: Vec<f64> = (0..1000)
+            .map(|_| SelectionPolicy::Thompson.score(&parent, &child, &mut rng))
+            .collect();
+        assert!(samples.iter().all(|&sample| (sample - 0.9).abs() < 0.05));
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        assert!((mean - 0.9).abs() < 0.005, "mean was {}", mean);
+
+        // The same RNG gives the same samples.
+        let mut rng = StdRng::seed_from_u64(0);
+        assert_eq!(
+            SelectionPolicy::Thompson.score(&parent, &child, &mut rng),
+            samples[0]
+        );
+    }
+
+    #[test]
+    fn thompson_clamps_negative_rewards() {
+        // Games can report rewards below 0 through their reward vectors,
+        // which leaves the child with a negative summed reward.
+        let parent = node(100, 0.0, 1.0);
+        let mut child = node(0, 0.0, 1.0);
+        for _ in 0..100 {
+            child.update(&RolloutResult::Rewards(vec![(Player::Red, -1.0)]), 0.5);
+        }
+        assert_eq!(child.wins, -100.0);
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let sample = SelectionPolicy::Thompson.score(&parent, &child, &mut rng);
+            assert!((0.0..0.1).contains(&sample), "sample was {}", sample);
+        }
+    }
+
+    #[test]
+    fn thompson_clamps_rewards_above_1() {
+        let parent = node(100, 0.0, 1.0);
+        let mut child = node(0, 0.0, 1.0);
+        for _ in 0..100 {
+            child.update(&RolloutResult::Rewards(vec![(Player::Red, 2.5)]), 0.5);
+        }
+        assert_eq!(child.wins, 250.0);
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let sample = SelectionPolicy::Thompson.score(&parent, &child, &mut rng);
+            assert!(sample > 0.9 && sample <= 1.0, "sample was {}", sample);
+        }
+    }
+
+    #[test]
+    fn thompson_samples_unvisited_children_uniformly() {
+        let parent = node(10, 5.0, 1.0);
+        let child = node(0, 0.0, 1.0);
+        let mut rng = StdRng::seed_from_u64(0);
+        let samples: Vec<f64> = (0..1000)
+            .map(|_| SelectionPolicy::Thompson.score(&parent, &child, &mut rng))
+            .collect();
+        assert!(samples.iter().any(|&sample| sample < 0.1));
+        assert!(samples.iter().any(|&sample| sample > 0.9));
+    }
+}
@@ -0,0 +1,260 @@
+//! The m,n,k-game, where players take turns placing a piece on any empty cell
+//! of an m by n board & the first to get k in a row wins. This is tic-tac-toe
+//! when m, n & k are all 3, and gomoku-style games on larger boards.
+
+use crate::board;
+use crate::game::{two_players, zobrist_piece_key, GameState, ZOBRIST_TURN_KEY};
+use std::fmt;
+use std::str::FromStr;
+
+two_players! {
+    /// The players available in the m,n,k-game.
+    pub enum Player {
+        X => "X",
+        O => "O",
+    }
+}
+
+/// The moves available in the m,n,k-game, which reference the cell that the
+/// player is placing their next piece in. Moves are written as the column
+/// letter followed by the row number, so `a1` is the top left cell.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "persist", derive(serde::Serialize, serde::Deserialize))]
+pub struct Move {
+    pub col: u8,
+    pub row: u8,
+}
+
+impl Move {
+    /// Constructs a move into the given cell.
+    pub fn new(col: u8, row: u8) -> Self {
+        Move { col, row }
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        board::fmt_cell(f, self.col, self.row)
+    }
+}
+
+impl fmt::Debug for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl FromStr for Move {
+    type Err = ParseMoveError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (col, row) = board::parse_cell(s).ok_or(ParseMoveError)?;
+        Ok(Move::new(col, row))
+    }
+}
+
+/// The error returned when a move can't be parsed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ParseMoveError;
+
+impl fmt::Display for ParseMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Moves are a column letter followed by a row number")
+    }
+}
+
+/// The move errors possible in the m,n,k-game.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MoveError {
+    OutOfRange(Move),
+    Occupied(Move),
+}
+
+impl fmt::Debug for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfRange(mv) => write!(f, "Move {} is out of range", mv),
+            Self::Occupied(mv) => write!(f, "Cell {} is already taken", mv),
+        }
+    }
+}
+
+/// The size of a tic-tac-toe board, which is also the length of line needed
+/// to win.
+pub const TIC_TAC_TOE_SIZE: usize = 3;
+
+/// The m,n,k-game state.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "persist", derive(serde::Serialize, serde::Deserialize))]
+pub struct Game {
+    turn: Player,
+    width: usize,
+    height: usize,
+    connect_len: usize,
+    /// The cells of the board, stored row by row with the top row first.
+    board: Vec<Option<Player>>,
+    winner: Option<Player>,
+    /// The Zobrist hash of the position, which is updated on every move.
+    hash: u64,
+}
+
+impl Game {
+    /// Constructs a new game of tic-tac-toe.
+    pub fn tic_tac_toe() -> Self {
+        Self::with_size(TIC_TAC_TOE_SIZE, TIC_TAC_TOE_SIZE, TIC_TAC_TOE_SIZE)
+    }
+
+    /// Constructs a new game on a board of the given size, where a line of
+    /// `connect_len` pieces wins.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the board has no cells, has more than 26 columns or 255 rows
+    /// (which moves can't reference), or if `connect_len` is 0.
+    pub fn with_size(width: usize, height: usize, connect_len: usize) -> Self {
+        assert!(
+            width > 0 && height > 0,
+            "The board must have at least one cell"
+        );
+        assert!(
+            width <= 26 && height <= u8::MAX as usize,
+            "The board can have at most 26 columns & 255 rows"
+        );
+        assert!(connect_len > 0, "The connect length must be at least 1");
+
+        Game {
+            turn: Player::X,
+            width,
+            height,
+            connect_len,
+            board: vec![None; width * height],
+            winner: None,
+            hash: 0,
+        }
+    }
+
+    /// Returns the number of columns on the board.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the number of rows on the board.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the length of line needed to win.
+    pub fn connect_len(&self) -> usize {
+        self.connect_len
+    }
+
+    /// Returns the piece in the given cell, where row 0 is the top row.
+    pub fn get_cell(&self, col: usize, row: usize) -> Option<Player> {
+        self.board[self.cell_index(col, row)]
+    }
+
+    /// Returns the index of the given cell in the board.
+    fn cell_index(&self, col: usize, row: usize) -> usize {
+        row * self.width + col
+    }
+
+    /// Returns whether the piece in the given cell is part of a line of at
+    /// least `connect_len`.
+    fn has_line_through(&self, col: usize, row: usize) -> bool {
+        let player = self.get_cell(col, row);
+        [(1, 0), (0, 1), (1, 1), (1, -1)]
+            .iter()
+            .any(|&(col_d, row_d)| {
+                // Counts the pieces that are the same in the given direction
+                // & its reverse.
+                let count_from = |sign: i64| {
+                    (1..)
+                        .map(|dist| {
+                            (
+                                col as i64 + col_d * dist * sign,
+                                row as i64 + row_d * dist * sign,
+                            )
+                        })
+                        .take_while(|&(c, r)| {
+                            c >= 0
+                                && c < self.width as i64
+                                && r >= 0
+                                && r < self.height as i64
+                                && self.get_cell(c as usize, r as usize) == player
+                        })
+                        .count()
+                };
+
+                1 + count_from(1) + count_from(-1) >= self.connect_len
+            })
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::tic_tac_toe()
+    }
+}
+
+impl GameState<Player, Move, MoveError> for Game {
+    fn make_move(&mut self, mv: Move) -> Result<(), MoveError> {
+        let (col, row) = (mv.col as usize, mv.row as usize);
+        if col >= self.width || row >= self.height {
+            return Err(MoveError::OutOfRange(mv));
+        }
+
+        let cell = self.cell_index(col, row);
+        if self.board[cell].is_some() {
+            return Err(MoveError::Occupied(mv));
+        }
+
+        self.board[cell] = Some(self.turn);
+        self.hash ^= zobrist_piece_key(cell, self.turn.index()) ^ ZOBRIST_TURN_KEY;
+        if self.has_line_through(col, row) {
+            self.winner = Some(self.turn);
+        }
+        self.turn = self.turn.next();
+        Ok(())
+    }
+
+    fn get_moves(&self) -> Vec<Move> {
+        match self.get_winner() {
+            Some(_) => vec![],
+            None => self
+                .board
+                .iter()
+                .enumerate()
+                .filter(|(_, cell)| cell.is_none())
+                .map(|(i, _)| Move::new((i % self.width) as u8, (i / self.width) as u8))
+                .collect(),
+        }
+    }
+
+    fn get_winner(&self) -> Option<Player> {
+        self.winner
+    }
+
+    fn get_current_player(&self) -> Player {
+        self.turn
+    }
+
+    fn get_prev_player(&self) -> Player {
+        self.turn.prev()
+    }
+
+    fn get_players(&self) -> Vec<Player> {
+        Player::all()
+    }
+
+    fn position_hash(&self) -> Option<u64> {
+        Some(self.hash)
+    }
+}
+
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        board::fmt_board(f, self.width, self.height, false, |col, row| {
+            self.get_cell(col, row)
+        })
+    }
+}
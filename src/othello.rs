@@ -0,0 +1,349 @@
+//! Othello (also known as Reversi), where players place discs on an 8x8 board
+//! & flip every line of opposing discs that the new disc closes off. A player
+//! that can't flip anything must pass, & the game ends once neither player can
+//! place a disc. Whoever has the most discs on the board wins.
+
+use crate::board;
+use crate::game::{two_players, zobrist_piece_key, GameState, ZOBRIST_TURN_KEY};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+two_players! {
+    /// The players available in Othello.
+    pub enum Player {
+        Black => "B",
+        White => "W",
+    }
+}
+
+/// The moves available in Othello. Placed discs are written as the column
+/// letter followed by the row number, so `a1` is the top left cell, & passes
+/// are written as `pass`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "persist", derive(serde::Serialize, serde::Deserialize))]
+pub enum Move {
+    /// Places a disc in the given cell.
+    Place { col: u8, row: u8 },
+    /// Passes the turn to the other player, which is only allowed when no disc
+    /// can be placed.
+    Pass,
+}
+
+impl Move {
+    /// Constructs a move that places a disc in the given cell.
+    pub fn place(col: u8, row: u8) -> Self {
+        Move::Place { col, row }
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Place { col, row } => board::fmt_cell(f, *col, *row),
+            Self::Pass => write!(f, "pass"),
+        }
+    }
+}
+
+impl fmt::Debug for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl FromStr for Move {
+    type Err = ParseMoveError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "pass" {
+            return Ok(Move::Pass);
+        }
+
+        let (col, row) = board::parse_cell(s).ok_or(ParseMoveError)?;
+        Ok(Move::place(col, row))
+    }
+}
+
+/// The error returned when a move can't be parsed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ParseMoveError;
+
+impl fmt::Display for ParseMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Moves are a column letter followed by a row number, or 'pass'"
+        )
+    }
+}
+
+/// The move errors possible in Othello.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MoveError {
+    OutOfRange(Move),
+    Occupied(Move),
+    /// The disc wouldn't flip any of the opponent's discs.
+    NoFlips(Move),
+    /// The player tried to pass while they could place a disc, or after the
+    /// game ended.
+    CannotPass,
+}
+
+impl fmt::Debug for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfRange(mv) => write!(f, "Move {} is out of range", mv),
+            Self::Occupied(mv) => write!(f, "Cell {} is already taken", mv),
+            Self::NoFlips(mv) => write!(f, "Move {} doesn't flip any discs", mv),
+            Self::CannotPass => write!(f, "Passing is only allowed when no disc can be placed"),
+        }
+    }
+}
+
+/// The number of rows & columns on the board.
+pub const BOARD_SIZE: usize = 8;
+
+/// The cells that aren't in the leftmost column.
+const NOT_LEFT_COL: u64 = !0x0101_0101_0101_0101;
+/// The cells that aren't in the rightmost column.
+const NOT_RIGHT_COL: u64 = !0x8080_8080_8080_8080;
+
+/// Shifts every cell in the given bitboard one step in the given direction,
+/// where 0 is up & each following direction is 45 degrees clockwise. Cells
+/// that would leave the board are dropped.
+fn shift(cells: u64, dir: usize) -> u64 {
+    match dir {
+        0 => cells >> 8,
+        1 => (cells >> 7) & NOT_LEFT_COL,
+        2 => (cells << 1) & NOT_LEFT_COL,
+        3 => (cells << 9) & NOT_LEFT_COL,
+        4 => cells << 8,
+        5 => (cells << 7) & NOT_RIGHT_COL,
+        6 => (cells >> 1) & NOT_RIGHT_COL,
+        7 => (cells >> 9) & NOT_RIGHT_COL,
+        _ => unreachable!("There are only 8 directions"),
+    }
+}
+
+/// The Othello game state.
+///
+/// Each player's discs are stored as a bitboard, where bit `row * 8 + col` is
+/// set if the player has a disc in that cell & row 0 is the top row.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "persist", derive(serde::Serialize, serde::Deserialize))]
+pub struct Game {
+    turn: Player,
+    discs: [u64; 2],
+    /// The Zobrist hash of the position, which is updated on every move.
+    hash: u64,
+}
+
+impl Game {
+    /// Constructs a new game with the standard starting position, where each
+    /// player has two discs placed diagonally in the centre & black moves
+    /// first.
+    pub fn new() -> Self {
+        let mut game = Game {
+            turn: Player::Black,
+            discs: [0, 0],
+            hash: 0,
+        };
+        for &(col, row, player) in [
+            (3, 3, Player::White),
+            (4, 4, Player::White),
+            (4, 3, Player::Black),
+            (3, 4, Player::Black),
+        ]
+        .iter()
+        {
+            let cell = Self::cell_index(col, row);
+            game.discs[player.index()] |= 1 << cell;
+            game.hash ^= zobrist_piece_key(cell, player.index());
+        }
+
+        game
+    }
+
+    /// Returns the disc in the given cell, where row 0 is the top row.
+    pub fn get_cell(&self, col: usize, row: usize) -> Option<Player> {
+        let bit = 1 << Self::cell_index(col, row);
+        Player::all()
+            .into_iter()
+            .find(|ply| self.discs[ply.index()] & bit != 0)
+    }
+
+    /// Returns the number of discs the given player has on the board.
+    pub fn count(&self, player: Player) -> u32 {
+        self.discs[player.index()].count_ones()
+    }
+
+    /// Returns the index of the given cell in the bitboards.
+    fn cell_index(col: usize, row: usize) -> usize {
+        row * BOARD_SIZE + col
+    }
+
+    /// Returns the cells that the given player could place a disc in.
+    fn placements(&self, player: Player) -> u64 {
+        let own = self.discs[player.index()];
+        let opp = self.discs[player.next().index()];
+        let empty = !(own | opp);
+
+        (0..8).fold(0, |placements, dir| {
+            // Spread along runs of opposing discs that start next to our own,
+            // & any empty cell at the end of a run can be placed in.
+            let mut run = shift(own, dir) & opp;
+            for _ in 0..BOARD_SIZE - 3 {
+                run |= shift(run, dir) & opp;
+            }
+            placements | (shift(run, dir) & empty)
+        })
+    }
+
+    /// Returns the opposing discs that would be flipped by the current player
+    /// placing a disc in the given cell.
+    fn flips(&self, cell: usize) -> u64 {
+        let own = self.discs[self.turn.index()];
+        let opp = self.discs[self.turn.next().index()];
+
+        (0..8).fold(0, |flips, dir| {
+            let mut run = 0;
+            let mut cur = shift(1 << cell, dir);
+            while cur & opp != 0 {
+                run |= cur;
+                cur = shift(cur, dir);
+            }
+
+            // The run is only flipped if it is closed off by our own disc.
+            if cur & own != 0 {
+                flips | run
+            } else {
+                flips
+            }
+        })
+    }
+
+    /// Returns whether neither player can place a disc, which ends the game.
+    fn is_over(&self) -> bool {
+        self.placements(self.turn) == 0 && self.placements(self.turn.next()) == 0
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GameState<Player, Move, MoveError> for Game {
+    fn make_move(&mut self, mv: Move) -> Result<(), MoveError> {
+        match mv {
+            Move::Place { col, row } => {
+                let (col, row) = (col as usize, row as usize);
+                if col >= BOARD_SIZE || row >= BOARD_SIZE {
+                    return Err(MoveError::OutOfRange(mv));
+                }
+
+                let cell = Self::cell_index(col, row);
+                if (self.discs[0] | self.discs[1]) & (1 << cell) != 0 {
+                    return Err(MoveError::Occupied(mv));
+                }
+                let flips = self.flips(cell);
+                if flips == 0 {
+                    return Err(MoveError::NoFlips(mv));
+                }
+
+                let (own, opp) = (self.turn, self.turn.next());
+                self.discs[own.index()] |= flips | (1 << cell);
+                self.discs[opp.index()] &= !flips;
+                self.hash ^= zobrist_piece_key(cell, own.index());
+                let mut flipped = flips;
+                while flipped != 0 {
+                    let cell = flipped.trailing_zeros() as usize;
+                    self.hash ^=
+                        zobrist_piece_key(cell, own.index()) ^ zobrist_piece_key(cell, opp.index());
+                    flipped &= flipped - 1;
+                }
+            }
+            Move::Pass => {
+                if self.placements(self.turn) != 0 || self.is_over() {
+                    return Err(MoveError::CannotPass);
+                }
+            }
+        }
+
+        self.hash ^= ZOBRIST_TURN_KEY;
+        self.turn = self.turn.next();
+        Ok(())
+    }
+
+    fn get_moves(&self) -> Vec<Move> {
+        let mut placements = self.placements(self.turn);
+        if placements == 0 {
+            // A player that can't place a disc must pass, unless the other
+            // player can't either, in which case the game is over.
+            return if self.placements(self.turn.next()) == 0 {
+                vec![]
+            } else {
+                vec![Move::Pass]
+            };
+        }
+
+        let mut moves = Vec::with_capacity(placements.count_ones() as usize);
+        while placements != 0 {
+            let cell = placements.trailing_zeros() as usize;
+            moves.push(Move::place(
+                (cell % BOARD_SIZE) as u8,
+                (cell / BOARD_SIZE) as u8,
+            ));
+            placements &= placements - 1;
+        }
+        moves
+    }
+
+    fn get_winner(&self) -> Option<Player> {
+        if !self.is_over() {
+            return None;
+        }
+
+        let (black, white) = (self.count(Player::Black), self.count(Player::White));
+        match black.cmp(&white) {
+            Ordering::Greater => Some(Player::Black),
+            Ordering::Less => Some(Player::White),
+            Ordering::Equal => None,
+        }
+    }
+
+    fn get_current_player(&self) -> Player {
+        self.turn
+    }
+
+    /// Passes are moves in their own right, so the turn always alternates &
+    /// the previous player is the one that made the last move, even if that
+    /// move was a pass.
+    fn get_prev_player(&self) -> Player {
+        self.turn.prev()
+    }
+
+    fn get_players(&self) -> Vec<Player> {
+        Player::all()
+    }
+
+    fn position_hash(&self) -> Option<u64> {
+        Some(self.hash)
+    }
+}
+
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        board::fmt_board(f, BOARD_SIZE, BOARD_SIZE, false, |col, row| {
+            self.get_cell(col, row)
+        })?;
+        write!(
+            f,
+            "\nB: {}, W: {}",
+            self.count(Player::Black),
+            self.count(Player::White)
+        )
+    }
+}
@@ -0,0 +1,286 @@
+//! Pig, a dice game where players take turns rolling a die as many times as
+//! they like, adding each roll to a turn total. Rolling a 1 loses the turn
+//! total & ends the turn, while holding adds it to the player's score. The
+//! first player to reach the target score wins.
+//!
+//! Each die roll is a chance event, so this is the reference game for chance
+//! nodes in the search.
+
+use crate::game::{two_players, GameState};
+use std::fmt;
+use std::str::FromStr;
+
+two_players! {
+    /// The players available in Pig.
+    pub enum Player {
+        One => "P1",
+        Two => "P2",
+    }
+}
+
+/// The moves available in Pig. Rolling & holding are written as `roll` &
+/// `hold`, and the outcome of a roll is written as the number on the die.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "persist", derive(serde::Serialize, serde::Deserialize))]
+pub enum Move {
+    /// Rolls the die, which is followed by the chance event of the roll.
+    Roll,
+    /// Adds the turn total to the player's score & ends the turn.
+    Hold,
+    /// The number that the die landed on.
+    Die(u8),
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Roll => write!(f, "roll"),
+            Self::Hold => write!(f, "hold"),
+            Self::Die(face) => write!(f, "{}", face),
+        }
+    }
+}
+
+impl fmt::Debug for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl FromStr for Move {
+    type Err = ParseMoveError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "roll" => Ok(Move::Roll),
+            "hold" => Ok(Move::Hold),
+            _ => s.parse().map(Move::Die).map_err(|_| ParseMoveError),
+        }
+    }
+}
+
+/// The error returned when a move can't be parsed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ParseMoveError;
+
+impl fmt::Display for ParseMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Moves are 'roll', 'hold' or the number rolled on the die"
+        )
+    }
+}
+
+/// The move errors possible in Pig.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MoveError {
+    /// The die is being rolled, so only its outcome can be played.
+    RollPending(Move),
+    /// The die outcome was played without the die being rolled.
+    NotRolling(Move),
+    /// The die outcome isn't a face of the die.
+    OutOfRange(Move),
+    /// The player tried to hold before rolling this turn.
+    NothingToHold,
+    GameOver,
+}
+
+impl fmt::Debug for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RollPending(mv) => write!(f, "Move {} isn't allowed while rolling", mv),
+            Self::NotRolling(mv) => write!(f, "Die outcome {} needs a roll first", mv),
+            Self::OutOfRange(mv) => write!(f, "Die outcome {} is out of range", mv),
+            Self::NothingToHold => write!(f, "Holding needs at least one roll first"),
+            Self::GameOver => write!(f, "The game is already over"),
+        }
+    }
+}
+
+/// The number of faces on the die.
+pub const DIE_SIDES: u8 = 6;
+
+/// The score needed to win a standard game.
+pub const DEFAULT_TARGET: u32 = 100;
+
+/// The Pig game state.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "persist", derive(serde::Serialize, serde::Deserialize))]
+pub struct Game {
+    turn: Player,
+    scores: [u32; 2],
+    /// The points rolled this turn that haven't been held yet.
+    turn_total: u32,
+    /// Whether the die has been rolled & is waiting on its outcome.
+    rolling: bool,
+    target: u32,
+    winner: Option<Player>,
+    /// The player that made the last move, which is the same as the current
+    /// player for most of a turn.
+    last_mover: Player,
+}
+
+impl Game {
+    /// Constructs a new game to the standard target score.
+    pub fn new() -> Self {
+        Self::with_target(DEFAULT_TARGET)
+    }
+
+    /// Constructs a new game to the given target score.
+    pub fn with_target(target: u32) -> Self {
+        Game {
+            turn: Player::One,
+            scores: [0, 0],
+            turn_total: 0,
+            rolling: false,
+            target,
+            winner: None,
+            last_mover: Player::Two,
+        }
+    }
+
+    /// Returns the score needed to win.
+    pub fn target(&self) -> u32 {
+        self.target
+    }
+
+    /// Returns the score that the given player has held.
+    pub fn score(&self, player: Player) -> u32 {
+        self.scores[player.index()]
+    }
+
+    /// Returns the points rolled by the current player this turn that haven't
+    /// been held yet.
+    pub fn turn_total(&self) -> u32 {
+        self.turn_total
+    }
+
+    /// Returns whether the die has been rolled & is waiting on its outcome.
+    pub fn is_rolling(&self) -> bool {
+        self.rolling
+    }
+
+    /// Adds the turn total to the current player's score.
+    fn bank(&mut self) {
+        self.scores[self.turn.index()] += self.turn_total;
+        self.turn_total = 0;
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GameState<Player, Move, MoveError> for Game {
+    fn make_move(&mut self, mv: Move) -> Result<(), MoveError> {
+        if self.winner.is_some() {
+            return Err(MoveError::GameOver);
+        }
+
+        match mv {
+            Move::Roll | Move::Hold if self.rolling => return Err(MoveError::RollPending(mv)),
+            Move::Roll => self.rolling = true,
+            Move::Hold => {
+                if self.turn_total == 0 {
+                    return Err(MoveError::NothingToHold);
+                }
+                self.bank();
+                self.turn = self.turn.next();
+            }
+            Move::Die(_) if !self.rolling => return Err(MoveError::NotRolling(mv)),
+            Move::Die(face) if face == 0 || face > DIE_SIDES => {
+                return Err(MoveError::OutOfRange(mv))
+            }
+            Move::Die(1) => {
+                self.rolling = false;
+                self.turn_total = 0;
+                self.turn = self.turn.next();
+            }
+            Move::Die(face) => {
+                self.rolling = false;
+                self.turn_total += face as u32;
+                // Reaching the target wins straight away, since there is no
+                // reason to keep rolling.
+                if self.score(self.turn) + self.turn_total >= self.target {
+                    self.bank();
+                    self.winner = Some(self.turn);
+                }
+            }
+        }
+
+        self.last_mover = match mv {
+            Move::Hold | Move::Die(1) => self.turn.prev(),
+            _ => self.turn,
+        };
+        Ok(())
+    }
+
+    fn get_moves(&self) -> Vec<Move> {
+        if self.winner.is_some() {
+            vec![]
+        } else if self.rolling {
+            (1..=DIE_SIDES).map(Move::Die).collect()
+        } else if self.turn_total == 0 {
+            vec![Move::Roll]
+        } else {
+            vec![Move::Roll, Move::Hold]
+        }
+    }
+
+    fn get_winner(&self) -> Option<Player> {
+        self.winner
+    }
+
+    fn get_current_player(&self) -> Player {
+        self.turn
+    }
+
+    /// The same player keeps moving until they hold or roll a 1, so the
+    /// previous player is tracked separately from the turn.
+    fn get_prev_player(&self) -> Player {
+        self.last_mover
+    }
+
+    fn get_players(&self) -> Vec<Player> {
+        Player::all()
+    }
+
+    /// Each face of the die is equally likely.
+    fn get_chance_outcomes(&self) -> Option<Vec<(Move, f64)>> {
+        if self.rolling {
+            Some(
+                (1..=DIE_SIDES)
+                    .map(|face| (Move::Die(face), 1.0 / DIE_SIDES as f64))
+                    .collect(),
+            )
+        } else {
+            None
+        }
+    }
+
+    // There is no position hash, since rolling a 1 on a turn total of 0 can
+    // repeat a position, which would make the search graph cyclic.
+}
+
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}, {}: {} (target {})\n{} turn total: {}",
+            Player::One,
+            self.score(Player::One),
+            Player::Two,
+            self.score(Player::Two),
+            self.target,
+            self.turn,
+            self.turn_total
+        )?;
+        if self.rolling {
+            write!(f, ", rolling")?;
+        }
+        Ok(())
+    }
+}
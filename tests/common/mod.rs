@@ -0,0 +1,140 @@
+//! Helpers shared by the tests of each game.
+
+// Each test file only uses the helpers it needs.
+#![allow(dead_code)]
+
+use mcts_impl::{GameState, Mcts, SearchLimit, SelectionPolicy};
+use std::fmt;
+use std::str::FromStr;
+
+/// Returns the game with the given moves played.
+pub fn play<P, M, ME, S>(mut game: S, moves: &[&str]) -> S
+where
+    P: Copy + PartialEq + ToString + fmt::Debug,
+    M: Copy + PartialEq + fmt::Debug + FromStr,
+    M::Err: fmt::Debug,
+    ME: Copy + fmt::Debug,
+    S: GameState<P, M, ME>,
+{
+    for mv in moves {
+        game.make_move(mv.parse().unwrap()).unwrap();
+    }
+    game
+}
+
+/// Checks that the game rejects each of the given moves with the given error,
+/// & that the rejected moves leave the game as it was.
+pub fn assert_rejects<P, M, ME, S>(game: &S, moves: &[(M, ME)])
+where
+    P: Copy + PartialEq + ToString + fmt::Debug,
+    M: Copy + PartialEq + fmt::Debug,
+    ME: Copy + PartialEq + fmt::Debug,
+    S: GameState<P, M, ME>,
+{
+    for &(mv, err) in moves {
+        let mut after = game.clone();
+        assert_eq!(after.make_move(mv), Err(err), "{:?} should be rejected", mv);
+        assert_eq!(after.to_string(), game.to_string());
+        assert_eq!(after.get_current_player(), game.get_current_player());
+    }
+}
+
+/// Checks that each move is written as the given text & parsed back from it,
+/// & that none of the invalid texts parse.
+pub fn assert_moves_round_trip<M>(moves: &[(&str, M)], invalid: &[&str])
+where
+    M: fmt::Display + fmt::Debug + FromStr + PartialEq,
+    M::Err: fmt::Debug,
+{
+    for (text, mv) in moves {
+        assert_eq!(&text.parse::<M>().unwrap(), mv);
+        assert_eq!(&mv.to_string(), text);
+    }
+    for text in invalid {
+        assert!(text.parse::<M>().is_err(), "{:?} should not parse", text);
+    }
+}
+
+/// A game played between one MCTS AI per player, which keeps every AI in sync
+/// with the moves played.
+pub struct SelfPlay<P, M, ME, S>
+where
+    P: Copy + PartialEq + ToString + fmt::Debug,
+    M: Copy + PartialEq + fmt::Debug,
+    ME: Copy + fmt::Debug,
+    S: GameState<P, M, ME>,
+{
+    pub game: S,
+    ais: Vec<(P, Mcts<P, M, ME, S>)>,
+    rounds: u64,
+}
+
+impl<P, M, ME, S> SelfPlay<P, M, ME, S>
+where
+    P: Copy + PartialEq + ToString + fmt::Debug,
+    M: Copy + PartialEq + fmt::Debug,
+    ME: Copy + fmt::Debug,
+    S: GameState<P, M, ME>,
+{
+    /// Sets up an AI for each of the game's players, which searches for the
+    /// given number of rounds per move. The AI of the player at index `i` is
+    /// seeded with `seed + i`.
+    pub fn new(game: S, seed: u64, rounds: u64) -> Self {
+        let ais = game
+            .get_players()
+            .into_iter()
+            .enumerate()
+            .map(|(i, ply)| (ply, Mcts::with_seed(ply, &game, seed + i as u64)))
+            .collect();
+        SelfPlay { game, ais, rounds }
+    }
+
+    /// Returns the move that the AI of the current player picks.
+    pub fn pick_move(&mut self) -> M {
+        let cur = self.cur_ai();
+        self.ais[cur]
+            .1
+            .select_next_move(
+                &SearchLimit::Rounds(self.rounds),
+                &SelectionPolicy::Ucb1(None),
+            )
+            .best_move
+    }
+
+    /// Plays the given move for the current player.
+    pub fn play_move(&mut self, mv: M) {
+        let cur = self.cur_ai();
+        self.game.make_move(mv).unwrap();
+        for (i, (_, ai)) in self.ais.iter_mut().enumerate() {
+            if i == cur {
+                ai.update_target_move(mv);
+            } else {
+                ai.update_opponent_move(mv);
+            }
+        }
+    }
+
+    /// Plays the given outcome of the pending chance event.
+    pub fn play_chance(&mut self, mv: M) {
+        self.game.make_move(mv).unwrap();
+        for (_, ai) in self.ais.iter_mut() {
+            ai.update_chance_move(mv);
+        }
+    }
+
+    /// Lets each AI pick the moves of its player until the game ends, &
+    /// returns the final state. The game must not have chance events.
+    pub fn finish(mut self) -> S {
+        while !self.game.get_moves().is_empty() {
+            let mv = self.pick_move();
+            self.play_move(mv);
+        }
+        self.game
+    }
+
+    /// Returns the index of the AI of the current player.
+    fn cur_ai(&self) -> usize {
+        let cur = self.game.get_current_player();
+        self.ais.iter().position(|&(ply, _)| ply == cur).unwrap()
+    }
+}
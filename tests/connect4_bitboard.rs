@@ -0,0 +1,54 @@
+use mcts_impl::connect4::{bitboard, Game};
+use mcts_impl::GameState;
+use proptest::prelude::*;
+
+/// Plays the same moves on both connect 4 implementations & checks that they
+/// agree after every move.
+fn check_agreement(
+    mut array: Game,
+    mut bits: bitboard::Game,
+    moves: Vec<u8>,
+) -> Result<(), TestCaseError> {
+    for mv in moves {
+        prop_assert_eq!(array.make_move(mv), bits.make_move(mv));
+        prop_assert_eq!(array.get_moves(), bits.get_moves());
+        prop_assert_eq!(array.get_winner(), bits.get_winner());
+        prop_assert_eq!(array.get_current_player(), bits.get_current_player());
+        prop_assert_eq!(array.to_string(), bits.to_string());
+    }
+    Ok(())
+}
+
+#[test]
+fn fits_checks_the_size_without_overflowing() {
+    assert!(bitboard::Game::fits(7, 6));
+    assert!(bitboard::Game::fits(8, 7));
+    assert!(!bitboard::Game::fits(8, 8));
+    assert!(!bitboard::Game::fits(2, usize::MAX));
+    assert!(!bitboard::Game::fits(usize::MAX, 1));
+}
+
+proptest! {
+    /// Checks the standard board, including moves that are out of range or
+    /// into full columns.
+    #[test]
+    fn bitboard_agrees_with_array_board(moves in prop::collection::vec(0u8..8, 0..80)) {
+        check_agreement(Game::new(), bitboard::Game::new(), moves)?;
+    }
+
+    /// Checks every board size that fits in a bitboard, with lines of any
+    /// length.
+    #[test]
+    fn bitboard_agrees_with_array_board_of_any_size(
+        (width, height) in (1usize..=8, 1usize..=8)
+            .prop_filter("must fit in a bitboard", |&(w, h)| bitboard::Game::fits(w, h)),
+        connect_len in 1usize..=9,
+        moves in prop::collection::vec(0u8..9, 0..80),
+    ) {
+        check_agreement(
+            Game::with_size(width, height, connect_len),
+            bitboard::Game::with_size(width, height, connect_len),
+            moves,
+        )?;
+    }
+}
@@ -0,0 +1,114 @@
+use mcts_impl::connect4::solver::{Solver, Value};
+use mcts_impl::connect4::{self, bitboard, Move};
+use mcts_impl::GameState;
+use proptest::prelude::*;
+
+/// Returns the game with the given moves played, ignoring illegal moves.
+fn play(mut game: bitboard::Game, moves: &[Move]) -> bitboard::Game {
+    for &mv in moves {
+        if game.get_moves().contains(&mv) {
+            game.make_move(mv).unwrap();
+        }
+    }
+    game
+}
+
+/// Returns the value of the position by searching every line of play.
+fn minimax(game: &bitboard::Game) -> i8 {
+    if game.get_winner().is_some() {
+        return -1;
+    }
+    game.get_moves()
+        .into_iter()
+        .map(|mv| {
+            let mut child = game.clone();
+            child.make_move(mv).unwrap();
+            -minimax(&child)
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+#[test]
+fn small_boards_are_draws() {
+    let mut solver = Solver::new();
+    assert_eq!(
+        solver.solve(&bitboard::Game::with_size(4, 4, 4)).value,
+        Value::Draw
+    );
+    assert_eq!(
+        solver.solve(&bitboard::Game::with_size(5, 4, 4)).value,
+        Value::Draw
+    );
+}
+
+#[test]
+fn takes_a_win_in_one() {
+    let game = play(bitboard::Game::with_size(5, 4, 4), &[0, 4, 0, 4, 0, 4]);
+    let solution = Solver::new().solve(&game);
+    assert_eq!(solution.value, Value::Win);
+    assert_eq!(solution.best_move, Some(0));
+}
+
+#[test]
+fn blocks_a_win_in_one() {
+    let game = play(bitboard::Game::with_size(5, 4, 4), &[0, 4, 0, 4, 0]);
+    let solution = Solver::new().solve(&game);
+    assert_eq!(solution.best_move, Some(0));
+}
+
+#[test]
+fn solves_the_array_backed_game() {
+    let mut game = connect4::Game::with_size(5, 4, 4);
+    for &mv in [0, 4, 0, 4, 0].iter() {
+        game.make_move(mv).unwrap();
+    }
+    let solution = Solver::new().solve_game(&game);
+    assert_eq!(solution.best_move, Some(0));
+    assert_eq!(
+        solution.value,
+        Solver::new().solve(&bitboard::Game::from(&game)).value
+    );
+}
+
+#[test]
+fn finished_games_have_no_best_move() {
+    let game = play(bitboard::Game::with_size(5, 4, 4), &[0, 4, 0, 4, 0, 4, 0]);
+    let solution = Solver::new().solve(&game);
+    assert_eq!(solution.value, Value::Loss);
+    assert_eq!(solution.best_move, None);
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    /// Checks the solver against a full search on a small board, and that the
+    /// best move keeps the value of the position.
+    #[test]
+    fn solver_agrees_with_minimax(moves in prop::collection::vec(0u8..4, 8..16)) {
+        let game = play(bitboard::Game::with_size(4, 4, 3), &moves);
+        let mut solver = Solver::new();
+        let solution = solver.solve(&game);
+
+        let expected = match minimax(&game) {
+            1 => Value::Win,
+            -1 => Value::Loss,
+            _ => Value::Draw,
+        };
+        prop_assert_eq!(solution.value, expected);
+
+        if let Some(mv) = solution.best_move {
+            let mut child = game.clone();
+            child.make_move(mv).unwrap();
+            let child_value = solver.solve(&child).value;
+            prop_assert_eq!(
+                child_value,
+                match expected {
+                    Value::Win => Value::Loss,
+                    Value::Loss => Value::Win,
+                    Value::Draw => Value::Draw,
+                }
+            );
+        }
+    }
+}
@@ -0,0 +1,177 @@
+mod common;
+
+use common::{assert_moves_round_trip, assert_rejects, play, SelfPlay};
+use mcts_impl::go::{Game, Move, MoveError, Player};
+use mcts_impl::{GameState, Mcts, SearchLimit, SelectionPolicy};
+
+/// The moves on a 5x5 board that lead to a ko, where white has just captured
+/// the black stone on c2 with b2.
+const KO: [&str; 8] = ["b1", "c1", "a2", "d2", "c2", "c3", "b3", "b2"];
+
+#[test]
+fn captures_groups_without_liberties() {
+    // White's b1 & a2 are separate stones that share their last liberty.
+    let game = play(Game::with_size(5, 7.5), &["c1", "b1", "b2", "a2", "a3"]);
+    assert_eq!(game.liberties(1, 0), Some(1));
+    assert_eq!(game.liberties(0, 1), Some(1));
+    assert_eq!(game.liberties(2, 0), Some(2));
+    assert_eq!(game.liberties(4, 4), None);
+
+    // White filling that liberty would capture nothing, so it's suicide.
+    assert_eq!(
+        game.from_move(Move::place(0, 0)),
+        Err(MoveError::Suicide(Move::place(0, 0)))
+    );
+
+    // Black filling it captures both stones at once.
+    let game = play(game, &["e5", "a1"]);
+    assert_eq!(game.get_cell(1, 0), None);
+    assert_eq!(game.get_cell(0, 1), None);
+    assert_eq!(game.get_cell(0, 0), Some(Player::Black));
+    assert_eq!(game.liberties(0, 0), Some(2));
+}
+
+#[test]
+fn rejects_illegal_moves() {
+    let game = play(Game::with_size(5, 7.5), &["c3"]);
+    assert_rejects(
+        &game,
+        &[
+            (Move::place(2, 2), MoveError::Occupied(Move::place(2, 2))),
+            (Move::place(5, 0), MoveError::OutOfRange(Move::place(5, 0))),
+        ],
+    );
+}
+
+#[test]
+fn parses_moves() {
+    assert_moves_round_trip(
+        &[
+            ("c3", Move::place(2, 2)),
+            ("s19", Move::place(18, 18)),
+            ("pass", Move::Pass),
+        ],
+        &["", "c0", "3c", "swap"],
+    );
+}
+
+#[test]
+fn ko_cant_be_retaken_straight_away() {
+    // White's b2 had no liberties of its own, but was allowed since it
+    // captured c2.
+    let mut game = play(Game::with_size(5, 7.5), &KO);
+    assert_eq!(game.get_cell(2, 1), None);
+    assert_eq!(game.liberties(1, 1), Some(1));
+
+    let retake = Move::place(2, 1);
+    assert_eq!(game.make_move(retake), Err(MoveError::Superko(retake)));
+    assert!(!game.get_moves().contains(&retake));
+
+    // After a move elsewhere from each player, the board is new & the ko can
+    // be retaken.
+    let mut game = play(game, &["e5", "e4"]);
+    assert!(game.get_moves().contains(&retake));
+    game.make_move(retake).unwrap();
+    assert_eq!(game.get_cell(1, 1), None);
+    assert_eq!(game.get_cell(2, 1), Some(Player::Black));
+}
+
+#[test]
+fn two_passes_end_the_game() {
+    let mut game = play(Game::with_size(5, 7.5), &["c3", "pass"]);
+    assert!(!game.is_over());
+    assert_eq!(game.get_winner(), None);
+
+    // A stone in between resets the passes.
+    let mut game_after_stone = play(game.clone(), &["b2", "pass"]);
+    assert!(!game_after_stone.is_over());
+    game_after_stone.make_move(Move::Pass).unwrap();
+    assert!(game_after_stone.is_over());
+
+    game.make_move(Move::Pass).unwrap();
+    assert!(game.is_over());
+    assert!(game.get_moves().is_empty());
+    assert_eq!(game.make_move(Move::Pass), Err(MoveError::GameOver));
+    assert_eq!(game.make_move(Move::place(0, 0)), Err(MoveError::GameOver));
+}
+
+#[test]
+fn area_scoring_counts_stones_territory_and_komi() {
+    // Black surrounds the whole board with a single stone.
+    let game = play(Game::with_size(3, 7.5), &["b2", "pass", "pass"]);
+    assert_eq!(game.score(Player::Black), 9.0);
+    assert_eq!(game.score(Player::White), 7.5);
+    assert_eq!(game.get_winner(), Some(Player::Black));
+
+    // Each player has a corner to themselves, while the points next to both
+    // colours are neutral, so komi decides the game.
+    let game = play(
+        Game::with_size(3, 0.5),
+        &["a2", "c2", "b1", "b3", "pass", "pass"],
+    );
+    assert_eq!(game.score(Player::Black), 3.0);
+    assert_eq!(game.score(Player::White), 3.5);
+    assert_eq!(game.get_winner(), Some(Player::White));
+
+    // Neither player can place on a single point board, so with no komi the
+    // game is drawn.
+    let game = play(Game::with_size(1, 0.0), &["pass", "pass"]);
+    assert_eq!(game.get_winner(), None);
+}
+
+#[test]
+fn rewards_grow_with_the_margin() {
+    // Black wins the 3x3 board by 1.5 points, & then by 9 with no komi.
+    let close = play(Game::with_size(3, 7.5), &["b2", "pass", "pass"]);
+    let rewards = close.get_rewards().unwrap();
+    let black = 0.9 + 0.1 * 1.5 / 9.0;
+    assert!((rewards[0].1 - black).abs() < 1e-9);
+    assert!((rewards[1].1 - (1.0 - black)).abs() < 1e-9);
+
+    let wide = play(Game::with_size(3, 0.0), &["b2", "pass", "pass"]);
+    assert_eq!(
+        wide.get_rewards(),
+        Some(vec![(Player::Black, 1.0), (Player::White, 0.0)])
+    );
+
+    // Unfinished & drawn games leave the rewards to the winner.
+    assert_eq!(Game::new().get_rewards(), None);
+    let drawn = play(Game::with_size(1, 0.0), &["pass", "pass"]);
+    assert_eq!(drawn.get_rewards(), None);
+}
+
+#[test]
+fn positions_are_never_shared() {
+    // Both orders reach the same board, but superko rules out different moves
+    // in each, so the positions can't be treated as the same.
+    let a = play(Game::with_size(5, 7.5), &["a1", "e5", "b1"]);
+    let b = play(Game::with_size(5, 7.5), &["b1", "e5", "a1"]);
+    assert_eq!(a.position_hash(), None);
+    assert_eq!(b.position_hash(), None);
+}
+
+#[test]
+fn mcts_passes_to_end_a_won_game() {
+    // After the other player passes, passing back ends the game, which wins
+    // for black with the whole board or for white with komi on an empty one.
+    let won_by_black = play(Game::with_size(3, 7.5), &["b2", "pass"]);
+    let won_by_white = play(Game::with_size(3, 7.5), &["pass"]);
+    for game in [won_by_black, won_by_white].iter() {
+        let mut ai = Mcts::with_seed(game.get_current_player(), game, 0);
+        let mv = ai
+            .select_next_move(&SearchLimit::Rounds(1000), &SelectionPolicy::Ucb1(None))
+            .best_move;
+        assert_eq!(mv, Move::Pass);
+        assert_eq!(
+            game.from_move(mv).unwrap().get_winner(),
+            Some(game.get_current_player())
+        );
+    }
+}
+
+#[test]
+fn mcts_plays_a_full_game() {
+    let game = SelfPlay::new(Game::with_size(5, 7.5), 0, 200).finish();
+    assert!(game.is_over());
+    assert!(game.get_winner().is_some());
+}
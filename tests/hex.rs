@@ -0,0 +1,180 @@
+mod common;
+
+use common::{assert_moves_round_trip, assert_rejects, play};
+use mcts_impl::hex::{Game, Move, MoveError, Player};
+use mcts_impl::{GameState, Mcts, SearchLimit, SelectionPolicy};
+use proptest::prelude::*;
+
+/// Returns the winner of the given board by flood filling from each player's
+/// first edge, without using the union-find of the game.
+fn flood_fill_winner(game: &Game) -> Option<Player> {
+    let size = game.size() as i64;
+    Player::all().into_iter().find(|&player| {
+        // Cells are (col, row), with red's first edge as the top row & blue's
+        // as the left column.
+        let on_edge = |col: i64, row: i64, first: bool| {
+            let line = if player == Player::Red { row } else { col };
+            line == if first { 0 } else { size - 1 }
+        };
+        let mut stack: Vec<(i64, i64)> = (0..size)
+            .flat_map(|a| (0..size).map(move |b| (a, b)))
+            .filter(|&(col, row)| {
+                on_edge(col, row, true) && game.get_cell(col as usize, row as usize) == Some(player)
+            })
+            .collect();
+        let mut seen = stack.clone();
+        while let Some((col, row)) = stack.pop() {
+            if on_edge(col, row, false) {
+                return true;
+            }
+            for &(col_d, row_d) in [(-1, 0), (1, 0), (0, -1), (1, -1), (-1, 1), (0, 1)].iter() {
+                let (c, r) = (col + col_d, row + row_d);
+                if c >= 0
+                    && c < size
+                    && r >= 0
+                    && r < size
+                    && !seen.contains(&(c, r))
+                    && game.get_cell(c as usize, r as usize) == Some(player)
+                {
+                    seen.push((c, r));
+                    stack.push((c, r));
+                }
+            }
+        }
+        false
+    })
+}
+
+/// Returns whether the player to move can force a win, by searching every
+/// line of play.
+fn is_winning(game: &Game) -> bool {
+    game.get_moves().into_iter().any(|mv| {
+        let child = game.from_move(mv).unwrap();
+        child.get_winner().is_some() || !is_winning(&child)
+    })
+}
+
+#[test]
+fn connects_along_the_shared_diagonal_only() {
+    // b1 & a2 touch, since each row is shifted right of the one above.
+    let game = play(Game::with_size(2), &["b1", "a1", "a2"]);
+    assert_eq!(game.get_winner(), Some(Player::Red));
+    assert!(game.get_moves().is_empty());
+
+    // a1 & b2 don't touch, so red hasn't connected the top & bottom yet.
+    let game = play(Game::with_size(2), &["a1", "b1", "b2"]);
+    assert_eq!(game.get_winner(), None);
+    let game = play(game, &["a2"]);
+    assert_eq!(game.get_winner(), Some(Player::Blue));
+}
+
+#[test]
+fn each_player_connects_their_own_edges() {
+    // Red fills the left column, which connects the top & bottom.
+    let game = play(Game::with_size(3), &["a1", "b1", "a2", "b2", "a3"]);
+    assert_eq!(game.get_winner(), Some(Player::Red));
+
+    // Blue fills the top row, which connects the left & right.
+    let game = play(Game::with_size(3), &["c3", "a1", "b3", "b1", "a3", "c1"]);
+    assert_eq!(game.get_winner(), Some(Player::Blue));
+}
+
+#[test]
+fn rejects_illegal_moves() {
+    let game = play(Game::with_size(3), &["b2"]);
+    assert_rejects(
+        &game,
+        &[
+            (Move::place(1, 1), MoveError::Occupied(Move::place(1, 1))),
+            (Move::place(3, 0), MoveError::OutOfRange(Move::place(3, 0))),
+            (Move::Swap, MoveError::CannotSwap),
+        ],
+    );
+    assert!(!game.get_moves().contains(&Move::Swap));
+}
+
+#[test]
+fn parses_moves() {
+    assert_moves_round_trip(
+        &[
+            ("c2", Move::place(2, 1)),
+            ("k11", Move::place(10, 10)),
+            ("swap", Move::Swap),
+        ],
+        &["c", "pass"],
+    );
+}
+
+#[test]
+fn swap_mirrors_the_first_stone_for_blue() {
+    let game = Game::with_size(5).with_swap_rule();
+    assert!(!game.get_moves().contains(&Move::Swap));
+
+    let mut game = play(game, &["b4"]);
+    assert!(game.get_moves().contains(&Move::Swap));
+    game.make_move(Move::Swap).unwrap();
+    assert_eq!(game.get_cell(1, 3), None);
+    assert_eq!(game.get_cell(3, 1), Some(Player::Blue));
+    assert_eq!(game.get_current_player(), Player::Red);
+    assert!(!game.get_moves().contains(&Move::Swap));
+
+    // The swapped stone is part of blue's connections.
+    let game = play(game, &["e1", "a2", "e3", "b2", "e5", "c2", "a5", "e2"]);
+    assert_eq!(game.get_winner(), Some(Player::Blue));
+}
+
+#[test]
+fn same_position_has_the_same_hash() {
+    let a = play(Game::with_size(5), &["a1", "e5", "c3"]);
+    let b = play(Game::with_size(5), &["c3", "e5", "a1"]);
+    assert_eq!(a.position_hash(), b.position_hash());
+    assert_ne!(a.position_hash(), Game::with_size(5).position_hash());
+}
+
+#[test]
+fn mcts_finds_a_winning_opening() {
+    // The search should prove the opening is won before running out of
+    // rounds, & pick a move that keeps the win.
+    let game = Game::with_size(3);
+    let mut ai = Mcts::with_seed(Player::Red, &game, 0);
+    let report = ai.select_next_move(&SearchLimit::Rounds(20_000), &SelectionPolicy::Ucb1(None));
+    assert!(report.rounds < 20_000);
+
+    let child = game.from_move(report.best_move).unwrap();
+    assert!(!is_winning(&child));
+}
+
+#[test]
+fn mcts_swaps_a_winning_opening() {
+    // Red's centre opening wins, so blue should take it over.
+    let game = play(Game::with_size(3).with_swap_rule(), &["b2"]);
+    let mut ai = Mcts::with_seed(Player::Blue, &game, 0);
+    let report = ai.select_next_move(&SearchLimit::Rounds(20_000), &SelectionPolicy::Ucb1(None));
+    assert_eq!(report.best_move, Move::Swap);
+    assert!(!is_winning(&game.from_move(Move::Swap).unwrap()));
+}
+
+proptest! {
+    /// Fills boards of any size in a random order, checking the union-find
+    /// against a flood fill after every move. The board can never fill up
+    /// without a winner.
+    #[test]
+    fn union_find_agrees_with_flood_fill(
+        cells in (1usize..=9).prop_flat_map(|size| {
+            Just((0..size * size).collect::<Vec<_>>())
+                .prop_shuffle()
+                .prop_map(move |cells| (size, cells))
+        }),
+    ) {
+        let (size, cells) = cells;
+        let mut game = Game::with_size(size);
+        for cell in cells {
+            if game.get_winner().is_some() {
+                break;
+            }
+            game.make_move(Move::place((cell % size) as u8, (cell / size) as u8)).unwrap();
+            prop_assert_eq!(game.get_winner(), flood_fill_winner(&game));
+        }
+        prop_assert!(game.get_winner().is_some());
+    }
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use common::{assert_moves_round_trip, assert_rejects, play, SelfPlay};
+use mcts_impl::mnk::{Game, Move, MoveError, Player};
+use mcts_impl::{GameState, Mcts, SearchLimit, SelectionPolicy};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+/// The number of rounds each MCTS search gets, which is enough to play
+/// tic-tac-toe perfectly.
+const ROUNDS: u64 = 5000;
+
+/// Plays a game of tic-tac-toe between MCTS, playing as the given player, &
+/// an opponent that plays uniformly random moves. Returns the winner.
+fn play_against_random(mcts_player: Player, seed: u64) -> Option<Player> {
+    let mut game = Game::tic_tac_toe();
+    let mut ai = Mcts::with_seed(mcts_player, &game, seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    while !game.get_moves().is_empty() {
+        if game.get_current_player() == mcts_player {
+            let mv = ai
+                .select_next_move(&SearchLimit::Rounds(ROUNDS), &SelectionPolicy::Ucb1(None))
+                .best_move;
+            game.make_move(mv).unwrap();
+            ai.update_target_move(mv);
+        } else {
+            let mv = *game.get_moves().choose(&mut rng).unwrap();
+            game.make_move(mv).unwrap();
+            ai.update_opponent_move(mv);
+        }
+    }
+
+    game.get_winner()
+}
+
+#[test]
+fn detects_lines_in_every_direction() {
+    let row = play(Game::tic_tac_toe(), &["a1", "a2", "b1", "b2", "c1"]);
+    assert_eq!(row.get_winner(), Some(Player::X));
+
+    let col = play(Game::tic_tac_toe(), &["a1", "b1", "a2", "b2", "c3", "b3"]);
+    assert_eq!(col.get_winner(), Some(Player::O));
+
+    let diag = play(Game::tic_tac_toe(), &["a1", "b1", "b2", "c1", "c3"]);
+    assert_eq!(diag.get_winner(), Some(Player::X));
+
+    let anti_diag = play(Game::tic_tac_toe(), &["c1", "a1", "b2", "b1", "a3"]);
+    assert_eq!(anti_diag.get_winner(), Some(Player::X));
+    assert!(anti_diag.get_moves().is_empty());
+}
+
+#[test]
+fn full_board_without_a_line_is_a_draw() {
+    let game = play(
+        Game::tic_tac_toe(),
+        &["a1", "b1", "c1", "b2", "a2", "a3", "c2", "c3", "b3"],
+    );
+    assert_eq!(game.get_winner(), None);
+    assert!(game.get_moves().is_empty());
+}
+
+#[test]
+fn rejects_illegal_moves() {
+    let game = play(Game::tic_tac_toe(), &["b2"]);
+    assert_rejects(
+        &game,
+        &[
+            (Move::new(1, 1), MoveError::Occupied(Move::new(1, 1))),
+            (Move::new(3, 0), MoveError::OutOfRange(Move::new(3, 0))),
+        ],
+    );
+}
+
+#[test]
+fn moves_round_trip_through_strings() {
+    assert_moves_round_trip(&[("c2", Move::new(2, 1))], &["2c", "a0"]);
+}
+
+#[test]
+fn gomoku_needs_the_full_line() {
+    let game = play(
+        Game::with_size(9, 9, 5),
+        &["a1", "a2", "b1", "b2", "c1", "c2", "d1", "d2"],
+    );
+    assert_eq!(game.get_winner(), None);
+    assert_eq!(play(game, &["e1"]).get_winner(), Some(Player::X));
+}
+
+#[test]
+fn mcts_never_loses_tic_tac_toe_against_random_play() {
+    for seed in 0..25 {
+        for &mcts_player in &[Player::X, Player::O] {
+            let winner = play_against_random(mcts_player, seed);
+            assert!(
+                winner.is_none() || winner == Some(mcts_player),
+                "MCTS lost as {} with seed {}",
+                mcts_player,
+                seed
+            );
+        }
+    }
+}
+
+#[test]
+fn mcts_draws_tic_tac_toe_against_itself() {
+    for seed in 0..5 {
+        let game = SelfPlay::new(Game::tic_tac_toe(), seed, ROUNDS).finish();
+        assert_eq!(game.get_winner(), None, "Game with seed {} was won", seed);
+    }
+}
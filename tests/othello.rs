@@ -0,0 +1,160 @@
+mod common;
+
+use common::{assert_moves_round_trip, assert_rejects, play, SelfPlay};
+use mcts_impl::othello::{Game, Move, MoveError, Player};
+use mcts_impl::{GameState, Mcts, SearchLimit, SelectionPolicy};
+use std::cmp::Ordering;
+
+/// The number of rounds each MCTS search gets. This isn't enough to play well,
+/// but plenty to check that the search handles passes.
+const ROUNDS: u64 = 200;
+
+/// The moves of the shortest possible game, where white is wiped out.
+const WIPEOUT: [&str; 9] = ["d3", "c3", "b3", "d2", "e1", "d6", "d7", "e3", "f4"];
+
+/// The moves leading to a position where black has to pass.
+const BLACK_PASSES: [&str; 8] = ["d3", "c3", "b3", "b2", "e6", "a3", "a1", "c1"];
+
+/// Returns every placement on the board.
+fn all_placements() -> impl Iterator<Item = Move> {
+    (0..8).flat_map(|row| (0..8).map(move |col| Move::place(col, row)))
+}
+
+#[test]
+fn starts_with_four_openings_for_black() {
+    let game = Game::new();
+    assert_eq!(game.get_current_player(), Player::Black);
+    assert_eq!(game.get_prev_player(), Player::White);
+
+    let mut moves: Vec<String> = game.get_moves().iter().map(Move::to_string).collect();
+    moves.sort();
+    assert_eq!(moves, ["c4", "d3", "e6", "f5"]);
+}
+
+#[test]
+fn flips_every_closed_off_line() {
+    let game = play(Game::new(), &["d3"]);
+    assert_eq!(game.get_cell(3, 2), Some(Player::Black));
+    assert_eq!(game.get_cell(3, 3), Some(Player::Black));
+    assert_eq!(game.count(Player::Black), 4);
+    assert_eq!(game.count(Player::White), 1);
+
+    // White's c3 closes off d4 diagonally, but not d3 since there is no white
+    // disc beyond it.
+    let game = play(game, &["c3"]);
+    assert_eq!(game.get_cell(3, 3), Some(Player::White));
+    assert_eq!(game.get_cell(3, 2), Some(Player::Black));
+    assert_eq!(game.count(Player::Black), 3);
+    assert_eq!(game.count(Player::White), 3);
+}
+
+#[test]
+fn rejects_illegal_moves() {
+    assert_rejects(
+        &Game::new(),
+        &[
+            (Move::place(3, 3), MoveError::Occupied(Move::place(3, 3))),
+            (Move::place(8, 0), MoveError::OutOfRange(Move::place(8, 0))),
+            (Move::place(0, 0), MoveError::NoFlips(Move::place(0, 0))),
+            (Move::Pass, MoveError::CannotPass),
+        ],
+    );
+}
+
+#[test]
+fn parses_moves() {
+    assert_moves_round_trip(
+        &[
+            ("d3", Move::place(3, 2)),
+            ("h8", Move::place(7, 7)),
+            ("pass", Move::Pass),
+        ],
+        &["", "d0", "3d"],
+    );
+}
+
+#[test]
+fn player_without_placements_must_pass() {
+    let mut game = play(Game::new(), &BLACK_PASSES);
+    assert_eq!(game.get_current_player(), Player::Black);
+    assert_eq!(game.get_moves(), [Move::Pass]);
+    assert_eq!(game.get_winner(), None);
+    for mv in all_placements() {
+        assert!(game.make_move(mv).is_err(), "{} should be illegal", mv);
+    }
+
+    // The pass counts as black's move, so white gets to place twice in a row.
+    let hash = game.position_hash();
+    game.make_move(Move::Pass).unwrap();
+    assert_eq!(game.get_current_player(), Player::White);
+    assert_eq!(game.get_prev_player(), Player::Black);
+    assert_ne!(game.position_hash(), hash);
+    assert!(!game.get_moves().contains(&Move::Pass));
+    assert_eq!(game.make_move(Move::Pass), Err(MoveError::CannotPass));
+}
+
+#[test]
+fn game_ends_when_neither_player_can_place() {
+    let mut game = play(Game::new(), &WIPEOUT);
+    assert!(game.get_moves().is_empty());
+    assert_eq!(game.get_winner(), Some(Player::Black));
+    assert_eq!(game.count(Player::Black), 13);
+    assert_eq!(game.count(Player::White), 0);
+    assert_eq!(game.make_move(Move::Pass), Err(MoveError::CannotPass));
+}
+
+#[test]
+fn same_position_has_the_same_hash() {
+    // Both orders flip the same discs, so they reach the same position.
+    let a = play(Game::new(), &["d3", "c3", "c4"]);
+    let b = play(Game::new(), &["c4", "c3", "d3"]);
+    assert_eq!(a, b);
+    assert_eq!(a.position_hash(), b.position_hash());
+    assert_ne!(a.position_hash(), Game::new().position_hash());
+}
+
+#[test]
+fn mcts_plays_through_passes() {
+    let start = play(Game::new(), &BLACK_PASSES[..BLACK_PASSES.len() - 1]);
+    let mut self_play = SelfPlay::new(start, 0, ROUNDS);
+
+    // White's last move is played first, which forces black to pass. Both
+    // trees then have to follow the pass & the repeated white turn.
+    self_play.play_move(BLACK_PASSES[BLACK_PASSES.len() - 1].parse().unwrap());
+    let mut passes = 0;
+    while !self_play.game.get_moves().is_empty() {
+        let mv = self_play.pick_move();
+        if mv == Move::Pass {
+            assert_eq!(self_play.game.get_moves(), [Move::Pass]);
+            passes += 1;
+        }
+        self_play.play_move(mv);
+    }
+
+    assert!(passes >= 1);
+    let game = self_play.game;
+    let (black, white) = (game.count(Player::Black), game.count(Player::White));
+    assert_eq!(
+        game.get_winner(),
+        match black.cmp(&white) {
+            Ordering::Greater => Some(Player::Black),
+            Ordering::Less => Some(Player::White),
+            Ordering::Equal => None,
+        }
+    );
+}
+
+#[test]
+fn mcts_starting_after_a_pass_works_for_either_player() {
+    // The root of a new tree belongs to the player that made the last move,
+    // which is black's pass here.
+    let game = play(Game::new(), &BLACK_PASSES)
+        .from_move(Move::Pass)
+        .unwrap();
+    for &ply in Player::all().iter() {
+        let mut ai = Mcts::with_seed(ply, &game, 0);
+        let report =
+            ai.select_next_move(&SearchLimit::Rounds(ROUNDS), &SelectionPolicy::Ucb1(None));
+        assert!(game.get_moves().contains(&report.best_move));
+    }
+}
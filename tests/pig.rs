@@ -0,0 +1,136 @@
+mod common;
+
+use common::{assert_moves_round_trip, assert_rejects, play, SelfPlay};
+use mcts_impl::pig::{Game, Move, MoveError, Player};
+use mcts_impl::{GameState, Mcts, SearchLimit, SelectionPolicy};
+
+#[test]
+fn rolls_add_to_the_turn_total_until_held() {
+    let game = play(Game::new(), &["roll"]);
+    assert!(game.is_rolling());
+    assert_eq!(game.get_moves().len(), 6);
+    let outcomes = game.get_chance_outcomes().unwrap();
+    assert_eq!(outcomes.len(), 6);
+    assert!((outcomes.iter().map(|&(_, p)| p).sum::<f64>() - 1.0).abs() < 1e-9);
+
+    // The player keeps the turn while rolling, so they are also the previous
+    // player.
+    let game = play(game, &["4", "roll", "5"]);
+    assert_eq!(game.turn_total(), 9);
+    assert_eq!(game.get_current_player(), Player::One);
+    assert_eq!(game.get_prev_player(), Player::One);
+    assert_eq!(game.get_chance_outcomes(), None);
+
+    let game = play(game, &["hold"]);
+    assert_eq!(game.score(Player::One), 9);
+    assert_eq!(game.turn_total(), 0);
+    assert_eq!(game.get_current_player(), Player::Two);
+    assert_eq!(game.get_prev_player(), Player::One);
+    assert_eq!(game.get_moves(), [Move::Roll]);
+}
+
+#[test]
+fn rolling_a_one_loses_the_turn_total() {
+    let game = play(Game::new(), &["roll", "6", "roll", "1"]);
+    assert_eq!(game.score(Player::One), 0);
+    assert_eq!(game.turn_total(), 0);
+    assert_eq!(game.get_current_player(), Player::Two);
+    assert_eq!(game.get_prev_player(), Player::One);
+}
+
+#[test]
+fn reaching_the_target_wins_straight_away() {
+    let game = play(Game::with_target(10), &["roll", "5", "roll", "3"]);
+    assert_eq!(game.get_winner(), None);
+
+    let mut game = play(game, &["roll", "2"]);
+    assert_eq!(game.get_winner(), Some(Player::One));
+    assert_eq!(game.score(Player::One), 10);
+    assert!(game.get_moves().is_empty());
+    assert_eq!(game.make_move(Move::Roll), Err(MoveError::GameOver));
+}
+
+#[test]
+fn rejects_illegal_moves() {
+    assert_rejects(
+        &Game::new(),
+        &[
+            (Move::Hold, MoveError::NothingToHold),
+            (Move::Die(3), MoveError::NotRolling(Move::Die(3))),
+        ],
+    );
+    assert_rejects(
+        &play(Game::new(), &["roll"]),
+        &[
+            (Move::Roll, MoveError::RollPending(Move::Roll)),
+            (Move::Hold, MoveError::RollPending(Move::Hold)),
+            (Move::Die(7), MoveError::OutOfRange(Move::Die(7))),
+        ],
+    );
+}
+
+#[test]
+fn parses_moves() {
+    assert_moves_round_trip(
+        &[
+            ("roll", Move::Roll),
+            ("hold", Move::Hold),
+            ("4", Move::Die(4)),
+            ("6", Move::Die(6)),
+        ],
+        &["pass"],
+    );
+}
+
+#[test]
+fn mcts_averages_over_die_rolls() {
+    // With a target of 2, any roll but a 1 wins, so the first player wins
+    // with a chance of 5/6 + 1/6 * (1 - p) = p, which is 6/7.
+    let game = Game::with_target(2);
+    let mut ai = Mcts::with_seed(Player::One, &game, 0);
+    let report = ai.select_next_move(&SearchLimit::Rounds(20_000), &SelectionPolicy::Ucb1(None));
+    assert_eq!(report.best_move, Move::Roll);
+    let win_rate = report.children[0].win_rate;
+    assert!(
+        (win_rate - 6.0 / 7.0).abs() < 0.02,
+        "win rate was {}",
+        win_rate
+    );
+}
+
+#[test]
+fn mcts_holds_a_big_turn_total() {
+    // Banking 60 points leaves the opponent far behind, while rolling risks
+    // throwing them away for a few more.
+    let game = play(Game::new(), &["roll", "6"].repeat(10));
+    assert_eq!(game.turn_total(), 60);
+    let mut ai = Mcts::with_seed(Player::One, &game, 0);
+    let report = ai.select_next_move(&SearchLimit::Rounds(5000), &SelectionPolicy::Ucb1(None));
+    assert_eq!(report.best_move, Move::Hold);
+}
+
+#[test]
+fn mcts_follows_die_rolls() {
+    let mut self_play = SelfPlay::new(Game::with_target(20), 0, 200);
+
+    // The die rolls are fixed so that the game always plays out the same way,
+    // apart from the choices of each player.
+    let mut faces = [3, 5, 1, 6, 2, 4].iter().cycle();
+    while !self_play.game.get_moves().is_empty() {
+        let mv = self_play.pick_move();
+        self_play.play_move(mv);
+        if self_play.game.is_rolling() {
+            self_play.play_chance(Move::Die(*faces.next().unwrap()));
+        }
+    }
+
+    assert!(self_play.game.get_winner().is_some());
+}
+
+#[test]
+#[should_panic(expected = "chance event")]
+fn mcts_cant_select_a_die_roll() {
+    let game = play(Game::new(), &["roll"]);
+    let mut ai = Mcts::with_seed(Player::One, &game, 0);
+    ai.select_next_move(&SearchLimit::Rounds(10), &SelectionPolicy::Ucb1(None));
+}